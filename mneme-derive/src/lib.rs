@@ -0,0 +1,252 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Field, Fields, Ident, ItemStruct, LitStr, Type, parse_macro_input,
+};
+
+/// Implements `mneme::Command` for a struct, leaving only `handle` to write.
+///
+/// The struct needs a `#[stream_id]` field (a `Uuid` or an `EventStreamId`) and may
+/// have a `#[state]` field holding the aggregate state; without one the state is
+/// `()`. `handle` is written as an inherent method with the `Command::handle`
+/// signature:
+///
+/// ```ignore
+/// #[command(event = AccountEvent, error = AccountError)]
+/// #[derive(Clone)]
+/// struct Withdraw {
+///     #[stream_id]
+///     id: Uuid,
+///     #[state]
+///     state: AccountState,
+///     amount: u32,
+/// }
+///
+/// impl Withdraw {
+///     fn handle(&self) -> Result<Vec<AccountEvent>, AccountError> {
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// `error` defaults to `std::convert::Infallible`.
+#[proc_macro_attribute]
+pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut event: Option<Type> = None;
+    let mut error: Option<Type> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("event") {
+            event = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("error") {
+            error = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `event` or `error`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+    let mut item = parse_macro_input!(input as ItemStruct);
+
+    expand_command(event, error, &mut item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_command(
+    event: Option<Type>,
+    error: Option<Type>,
+    item: &mut ItemStruct,
+) -> syn::Result<TokenStream2> {
+    let event = event.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "expected `#[command(event = EventType)]`",
+        )
+    })?;
+    let error = match error {
+        Some(error) => quote!(#error),
+        None => quote!(::std::convert::Infallible),
+    };
+
+    let Fields::Named(fields) = &mut item.fields else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[command] requires a struct with named fields",
+        ));
+    };
+
+    let mut stream_id: Option<(Ident, Type)> = None;
+    let mut state: Option<(Ident, Type)> = None;
+    for field in fields.named.iter_mut() {
+        if take_marker(field, "stream_id") {
+            set_once(&mut stream_id, field, "stream_id")?;
+        }
+        if take_marker(field, "state") {
+            set_once(&mut state, field, "state")?;
+        }
+    }
+
+    let (id_field, id_type) = stream_id.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &item.ident,
+            "#[command] requires a field marked #[stream_id]",
+        )
+    })?;
+    let event_stream_id = if is_named(&id_type, "EventStreamId") {
+        quote!(self.#id_field.clone())
+    } else {
+        quote!(::mneme::EventStreamId::from_uuid(self.#id_field))
+    };
+
+    let (state_type, get_state, set_state) = match state {
+        Some((field, ty)) => (
+            quote!(#ty),
+            quote!(self.#field.clone()),
+            quote!(self.#field = state.clone();),
+        ),
+        None => (quote!(()), quote!(), quote!(let _ = state;)),
+    };
+
+    let ident = &item.ident;
+    let (impl_generics, type_generics, where_clause) = item.generics.split_for_impl();
+    Ok(quote! {
+        #item
+
+        impl #impl_generics ::mneme::Command for #ident #type_generics #where_clause {
+            type Event = #event;
+            type State = #state_type;
+            type Error = #error;
+
+            fn handle(&self) -> ::std::result::Result<::std::vec::Vec<Self::Event>, Self::Error> {
+                Self::handle(self)
+            }
+
+            fn event_stream_id(&self) -> ::mneme::EventStreamId {
+                #event_stream_id
+            }
+
+            fn get_state(&self) -> Self::State {
+                #get_state
+            }
+
+            fn set_state(&mut self, state: &Self::State) {
+                #set_state
+            }
+        }
+    })
+}
+
+/// Removes a bare `#[name]` marker from the field, reporting whether it was there.
+fn take_marker(field: &mut Field, name: &str) -> bool {
+    let before = field.attrs.len();
+    field.attrs.retain(|attr| !attr.path().is_ident(name));
+    field.attrs.len() != before
+}
+
+fn set_once(slot: &mut Option<(Ident, Type)>, field: &Field, name: &str) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new_spanned(
+            field,
+            format!("only one field may be marked #[{name}]"),
+        ));
+    }
+    let ident = field.ident.clone().expect("named fields have identifiers");
+    *slot = Some((ident, field.ty.clone()));
+    Ok(())
+}
+
+fn is_named(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Implements `mneme::Event`.
+///
+/// On an enum each variant's event type is `Enum.Variant`; on a struct it is the
+/// struct's name. Either can be overridden with `#[event(type = "...")]` on the
+/// variant or struct. `all_event_types` lists every type so reads reject
+/// anything else, and `event_type_table` pairs each with its variant's name.
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_event(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_event(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let (event_type, names, types) = match &input.data {
+        Data::Struct(_) => {
+            let name = type_override(&input.attrs)?.unwrap_or_else(|| ident.to_string());
+            (
+                quote!(#name.to_string()),
+                vec![ident.to_string()],
+                vec![name],
+            )
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            let mut names = Vec::new();
+            let mut types = Vec::new();
+            for variant in &data.variants {
+                let variant_ident = &variant.ident;
+                let name = type_override(&variant.attrs)?
+                    .unwrap_or_else(|| format!("{ident}.{variant_ident}"));
+                arms.push(quote!(Self::#variant_ident { .. } => #name.to_string(),));
+                names.push(variant_ident.to_string());
+                types.push(name);
+            }
+            (quote!(match self { #(#arms)* }), names, types)
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "#[derive(Event)] supports structs and enums",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::mneme::Event for #ident #type_generics #where_clause {
+            fn event_type(&self) -> ::std::string::String {
+                #event_type
+            }
+
+            fn all_event_types() -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(#types.to_string()),*]
+            }
+
+            fn event_type_table() -> ::std::vec::Vec<(&'static str, &'static str)> {
+                ::std::vec![#((#names, #types)),*]
+            }
+        }
+    })
+}
+
+fn type_override(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    let mut event_type = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("event")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type") {
+                let value: LitStr = meta.value()?.parse()?;
+                event_type = Some(value.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `type`"))
+            }
+        })?;
+    }
+    Ok(event_type)
+}
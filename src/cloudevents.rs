@@ -0,0 +1,196 @@
+use std::fmt;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::dyn_event_store::{DynEventStore, RawEvent, RawEventStream};
+use crate::{Error, EventStreamId, EventStreamVersion};
+
+const SPEC_VERSION: &str = "1.0";
+const CONTENT_TYPE: &str = "application/cloudevents+json";
+
+type IdGenerator = Arc<dyn Fn(&RawEvent) -> String + Send + Sync>;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    specversion: String,
+    id: String,
+    source: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    datacontenttype: String,
+    data: serde_json::Value,
+}
+
+/// Wraps serialized events in a structured-mode CloudEvents JSON envelope and
+/// unwraps them again on read.
+#[derive(Clone)]
+pub struct CloudEventsSerializer {
+    source: String,
+    id_generator: IdGenerator,
+}
+
+impl fmt::Debug for CloudEventsSerializer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloudEventsSerializer")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CloudEventsSerializer {
+    /// Envelope ids default to the event's id when it has one (see
+    /// `Command::dedup_key`), and a random UUID otherwise.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            id_generator: Arc::new(|event: &RawEvent| {
+                event.id.unwrap_or_else(Uuid::new_v4).to_string()
+            }),
+        }
+    }
+
+    pub fn with_id_generator(
+        mut self,
+        generator: impl Fn(&RawEvent) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.id_generator = Arc::new(generator);
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn wrap(&self, event: RawEvent) -> Result<RawEvent, Error> {
+        let envelope = Envelope {
+            specversion: SPEC_VERSION.to_string(),
+            id: (self.id_generator)(&event),
+            source: self.source.clone(),
+            event_type: event.event_type.clone(),
+            datacontenttype: event.content_type,
+            data: serde_json::from_slice(&event.data)?,
+        };
+        Ok(RawEvent {
+            event_type: event.event_type,
+            content_type: CONTENT_TYPE.to_string(),
+            data: serde_json::to_vec(&envelope)?.into(),
+            metadata: event.metadata,
+            id: event.id,
+        })
+    }
+
+    pub fn unwrap(&self, event: RawEvent) -> Result<RawEvent, Error> {
+        let envelope: Envelope = serde_json::from_slice(&event.data)?;
+        Ok(RawEvent {
+            event_type: envelope.event_type,
+            content_type: envelope.datacontenttype,
+            data: serde_json::to_vec(&envelope.data)?.into(),
+            metadata: event.metadata,
+            id: event.id,
+        })
+    }
+}
+
+/// A [`DynEventStore`] that writes every event through a [`CloudEventsSerializer`]
+/// and unwraps envelopes on read, so commands keep working with their own types.
+#[derive(Clone)]
+pub struct CloudEventsStore {
+    inner: Arc<dyn DynEventStore>,
+    serializer: CloudEventsSerializer,
+}
+
+impl CloudEventsStore {
+    pub fn new(inner: Arc<dyn DynEventStore>, serializer: CloudEventsSerializer) -> Self {
+        Self { inner, serializer }
+    }
+}
+
+impl DynEventStore for CloudEventsStore {
+    fn publish_raw(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let events = events
+                .into_iter()
+                .map(|event| self.serializer.wrap(event))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.inner
+                .publish_raw(stream_id, events, expected_version)
+                .await
+        })
+    }
+
+    fn read_raw(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<RawEventStream, Error>> {
+        Box::pin(async move {
+            let serializer = self.serializer.clone();
+            let events = self.inner.read_raw(stream_id).await?;
+            Ok(events
+                .map(move |record| {
+                    let (event, version) = record?;
+                    Ok((serializer.unwrap(event)?, version))
+                })
+                .boxed())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventStore, InMemoryEventStore};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Deposited {
+        amount: u32,
+    }
+
+    impl crate::Event for Deposited {
+        fn event_type(&self) -> String {
+            "Deposited".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trips_events_through_the_envelope() {
+        let inner = InMemoryEventStore::new();
+        let serializer = CloudEventsSerializer::new("urn:example:accounts")
+            .with_id_generator(|_| "fixed-id".to_string());
+        let store: Arc<dyn DynEventStore> =
+            Arc::new(CloudEventsStore::new(Arc::new(inner.clone()), serializer));
+        let stream_id = EventStreamId::new();
+
+        store
+            .publish(stream_id.clone(), vec![Deposited { amount: 10 }], None)
+            .await
+            .unwrap();
+
+        let mut raw = inner.read_raw(stream_id.clone()).await.unwrap();
+        let (stored, _) = raw.next().await.unwrap().unwrap();
+        assert_eq!(stored.content_type, CONTENT_TYPE);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&stored.data).unwrap(),
+            serde_json::json!({
+                "specversion": "1.0",
+                "id": "fixed-id",
+                "source": "urn:example:accounts",
+                "type": "Deposited",
+                "datacontenttype": "application/json",
+                "data": { "amount": 10 },
+            })
+        );
+
+        let mut events = store.read_stream::<Deposited>(stream_id).await.unwrap();
+        assert_eq!(
+            events.next().await.unwrap(),
+            Some((Deposited { amount: 10 }, EventStreamVersion::new(0)))
+        );
+        assert_eq!(events.next().await.unwrap(), None);
+    }
+}
@@ -1,21 +1,82 @@
 use crate::EventStreamVersion;
 use crate::event::Event;
 use crate::event_store::EventStreamId;
+use crate::event_store::LogPosition;
+use futures::future::BoxFuture;
 use std::fmt::Debug;
+use std::time::Duration;
+use uuid::Uuid;
 
 pub trait Command: Clone {
     type Event: Event;
     type State: AggregateState<Self::Event>;
     type Error: std::error::Error + Send + Sync + 'static;
 
+    /// Decides which events to append given the replayed state. A panic here is
+    /// caught and reported as `Error::CommandFailed` rather than unwinding
+    /// through `execute`.
     fn handle(&self) -> Result<Vec<Self::Event>, Self::Error>;
 
+    /// Checks the replayed state before `handle` runs. An error rejects the
+    /// command with `Error::CommandFailed` without calling `handle`.
+    fn precondition(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Whether an error from `prepare`, `precondition` or `handle` means the
+    /// command no longer applies, so retrying it anywhere is pointless. `execute`
+    /// then stops with `Error::CommandAbandoned` instead of `Error::CommandFailed`.
+    fn abandons(&self, _error: &Self::Error) -> bool {
+        false
+    }
+
+    /// Async work `handle` depends on that doesn't need the stream, such as
+    /// fetching reference data. `execute` runs it once, concurrently with the first
+    /// read of the stream, and calls the returned closure on the replayed command
+    /// before `handle` so it can store the result. The future can't borrow the
+    /// command; clone whatever it needs. Defaults to doing nothing.
+    fn prepare(&self) -> BoxFuture<'static, Result<Prepared<Self>, Self::Error>>
+    where
+        Self: Sized,
+    {
+        Box::pin(std::future::ready(Ok(
+            Box::new(|_: &mut Self| {}) as Prepared<Self>
+        )))
+    }
+
     fn event_stream_id(&self) -> EventStreamId;
 
     fn get_state(&self) -> Self::State;
 
     fn set_state(&mut self, state: &Self::State);
 
+    /// A key identifying the input this command was built from. When present, the
+    /// ids of the published events are derived from it, so replicas handling the
+    /// same input append identical events. KurrentDB only treats such a repeated
+    /// append as idempotent when it is made against the same exact expected
+    /// version; appends to a stream that does not exist yet use `Any` and are
+    /// not deduplicated.
+    fn dedup_key(&self) -> Option<Uuid> {
+        None
+    }
+
+    /// Whether `handle` depends on the stream's history. Stateless commands return
+    /// `false`, so `execute` only probes the stream's head version to append
+    /// against instead of reading every event; no snapshot is loaded and nothing
+    /// is applied to the state.
+    fn needs_replay(&self) -> bool {
+        true
+    }
+
+    /// Whether events written by others since this command's stream was read
+    /// invalidate its decision. When they don't, `execute` applies them and appends
+    /// the same events again at the new head without calling `handle`; otherwise it
+    /// retries in full. Defaults to treating every concurrent write as a conflict.
+    fn conflicts_with(&self, _new_events: &[Self::Event]) -> bool {
+        true
+    }
+
+    #[deprecated(note = "implement `on_retry`, which also receives the conflict")]
     fn mark_retry(&self) -> Self
     where
         Self: Sized + Clone,
@@ -23,6 +84,16 @@ pub trait Command: Clone {
         self.clone()
     }
 
+    /// Called after a version conflict to produce the command for the next attempt.
+    /// Defaults to `mark_retry`.
+    fn on_retry(&self, _context: &RetryContext) -> Self
+    where
+        Self: Sized + Clone,
+    {
+        #[allow(deprecated)]
+        self.mark_retry()
+    }
+
     fn override_expected_version(&self) -> Option<EventStreamVersion> {
         None
     }
@@ -31,16 +102,104 @@ pub trait Command: Clone {
     where
         Self: Sized,
     {
-        self.set_state(self.get_state().apply(event));
+        let state = self.get_state().apply(event);
+        self.set_state(&state);
     }
 }
 
+/// Stores the result of `Command::prepare` on the command.
+pub type Prepared<C> = Box<dyn FnOnce(&mut C) + Send>;
+
+/// Why `execute` is retrying a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryContext {
+    /// The retry about to be made, starting at 1.
+    pub attempt: u32,
+    /// The version the conflicting append expected.
+    pub expected: Option<EventStreamVersion>,
+    /// The version the stream was actually at, or `None` if it doesn't exist.
+    pub actual: Option<EventStreamVersion>,
+}
+
 pub trait AggregateState<E: Event>: Debug + Sized {
-    fn apply(&mut self, event: &E) -> &Self;
+    fn apply(&self, event: &E) -> Self;
+
+    /// Serializes the state for a snapshot. States that return `None` (the
+    /// default) are never snapshotted.
+    fn to_snapshot(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Rebuilds the state from a value produced by `to_snapshot`.
+    fn from_snapshot(_snapshot: &serde_json::Value) -> Option<Self> {
+        None
+    }
 }
 
 impl<E: Event> AggregateState<E> for () {
-    fn apply(&mut self, _: &E) -> &Self {
-        self
+    fn apply(&self, _: &E) -> Self {}
+}
+
+/// What `execute` did with a command. `replayed_events` counts the events applied
+/// to build the state the command acted on, excluding any covered by a snapshot.
+/// `per_attempt_delays` holds the backoff slept before each retry, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandOutcome<E> {
+    /// `handle` returned events and they were appended, ending at `version`.
+    /// `positions` holds each event's version and global log position when
+    /// `ExecuteConfig::with_event_positions` is set, and is empty otherwise.
+    Committed {
+        events: Vec<E>,
+        version: EventStreamVersion,
+        replayed_events: usize,
+        positions: Vec<(EventStreamVersion, LogPosition)>,
+        per_attempt_delays: Vec<Duration>,
+    },
+    /// `handle` returned no events. `version` is the head the command was
+    /// evaluated against, or `None` if the stream doesn't exist.
+    NoOp {
+        version: Option<EventStreamVersion>,
+        replayed_events: usize,
+        per_attempt_delays: Vec<Duration>,
+    },
+}
+
+impl<E> CommandOutcome<E> {
+    pub fn is_noop(&self) -> bool {
+        matches!(self, CommandOutcome::NoOp { .. })
+    }
+
+    pub fn version(&self) -> Option<EventStreamVersion> {
+        match self {
+            CommandOutcome::Committed { version, .. } => Some(*version),
+            CommandOutcome::NoOp { version, .. } => *version,
+        }
+    }
+
+    pub fn replayed_events(&self) -> usize {
+        match self {
+            CommandOutcome::Committed {
+                replayed_events, ..
+            }
+            | CommandOutcome::NoOp {
+                replayed_events, ..
+            } => *replayed_events,
+        }
+    }
+
+    pub fn per_attempt_delays(&self) -> &[Duration] {
+        match self {
+            CommandOutcome::Committed {
+                per_attempt_delays, ..
+            }
+            | CommandOutcome::NoOp {
+                per_attempt_delays, ..
+            } => per_attempt_delays,
+        }
+    }
+
+    /// The total time spent sleeping between attempts.
+    pub fn total_backoff(&self) -> Duration {
+        self.per_attempt_delays().iter().sum()
     }
 }
@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use futures::future::BoxFuture;
+
+use crate::dyn_event_store::{DynEventStore, RawEvent, RawEventStream};
+use crate::{Error, EventStreamId, EventStreamVersion};
+
+const ZSTD_SUFFIX: &str = "+zstd";
+
+/// Compresses serialized events of at least `threshold` bytes with zstd and tags
+/// their content type with a `+zstd` suffix (`application/json+zstd`), so they can
+/// be recognized and decompressed on read. Smaller events pass through unchanged.
+#[derive(Debug, Clone, Copy)]
+pub struct ZstdCompressor {
+    threshold: usize,
+    level: i32,
+}
+
+impl ZstdCompressor {
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            level: zstd::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn compress(&self, event: RawEvent) -> Result<RawEvent, Error> {
+        if event.data.len() < self.threshold || event.content_type.ends_with(ZSTD_SUFFIX) {
+            return Ok(event);
+        }
+        let data = zstd::encode_all(&event.data[..], self.level)
+            .map_err(|source| Error::CompressionFailed { source })?;
+        Ok(RawEvent {
+            content_type: format!("{}{}", event.content_type, ZSTD_SUFFIX),
+            data: data.into(),
+            ..event
+        })
+    }
+
+    pub fn decompress(&self, event: RawEvent) -> Result<RawEvent, Error> {
+        let Some(content_type) = event.content_type.strip_suffix(ZSTD_SUFFIX) else {
+            return Ok(event);
+        };
+        let data = decompressed(&event.content_type, &event.data)?.into_owned();
+        Ok(RawEvent {
+            content_type: content_type.to_string(),
+            data: data.into(),
+            ..event
+        })
+    }
+}
+
+/// `data` decompressed if `content_type` carries the `+zstd` suffix, otherwise
+/// as is. Typed reads go through this, so compressed events decode even when
+/// they weren't read through a `CompressingStore`.
+pub(crate) fn decompressed<'a>(content_type: &str, data: &'a [u8]) -> Result<Cow<'a, [u8]>, Error> {
+    if !content_type.ends_with(ZSTD_SUFFIX) {
+        return Ok(Cow::Borrowed(data));
+    }
+    zstd::decode_all(data)
+        .map(Cow::Owned)
+        .map_err(|source| Error::CompressionFailed { source })
+}
+
+/// A [`DynEventStore`] that compresses large events through a [`ZstdCompressor`]
+/// and decompresses them on read, so commands keep working with their own types.
+#[derive(Clone)]
+pub struct CompressingStore {
+    inner: Arc<dyn DynEventStore>,
+    compressor: ZstdCompressor,
+}
+
+impl CompressingStore {
+    pub fn new(inner: Arc<dyn DynEventStore>, compressor: ZstdCompressor) -> Self {
+        Self { inner, compressor }
+    }
+}
+
+impl DynEventStore for CompressingStore {
+    fn publish_raw(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let events = events
+                .into_iter()
+                .map(|event| self.compressor.compress(event))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.inner
+                .publish_raw(stream_id, events, expected_version)
+                .await
+        })
+    }
+
+    fn read_raw(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<RawEventStream, Error>> {
+        Box::pin(async move {
+            let compressor = self.compressor;
+            let events = self.inner.read_raw(stream_id).await?;
+            Ok(events
+                .map(move |record| {
+                    let (event, version) = record?;
+                    Ok((compressor.decompress(event)?, version))
+                })
+                .boxed())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{EventStore, InMemoryEventStore};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct DocumentAttached {
+        body: String,
+    }
+
+    impl crate::Event for DocumentAttached {
+        fn event_type(&self) -> String {
+            "DocumentAttached".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn compresses_large_events_transparently() {
+        let inner = InMemoryEventStore::new();
+        let store: Arc<dyn DynEventStore> = Arc::new(CompressingStore::new(
+            Arc::new(inner.clone()),
+            ZstdCompressor::new(1024),
+        ));
+        let stream_id = EventStreamId::new();
+        let small = DocumentAttached {
+            body: "short".to_string(),
+        };
+        let large = DocumentAttached {
+            body: "lorem ipsum ".repeat(1000),
+        };
+
+        store
+            .publish(stream_id.clone(), vec![small, large], None)
+            .await
+            .unwrap();
+
+        let mut raw = inner.read_raw(stream_id.clone()).await.unwrap();
+        let (stored, _) = raw.next().await.unwrap().unwrap();
+        assert_eq!(stored.content_type, "application/json");
+        let (stored, _) = raw.next().await.unwrap().unwrap();
+        assert_eq!(stored.content_type, "application/json+zstd");
+        assert!(stored.data.len() < 1024);
+
+        let mut events = store
+            .read_stream::<DocumentAttached>(stream_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            events.next().await.unwrap(),
+            Some((
+                DocumentAttached {
+                    body: "short".to_string()
+                },
+                EventStreamVersion::new(0)
+            ))
+        );
+        assert_eq!(
+            events.next().await.unwrap(),
+            Some((
+                DocumentAttached {
+                    body: "lorem ipsum ".repeat(1000)
+                },
+                EventStreamVersion::new(1)
+            ))
+        );
+        assert_eq!(events.next().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn compressed_events_decode_without_the_wrapper_store() {
+        let inner = InMemoryEventStore::new();
+        let store: Arc<dyn DynEventStore> = Arc::new(CompressingStore::new(
+            Arc::new(inner.clone()),
+            ZstdCompressor::new(1024),
+        ));
+        let stream_id = EventStreamId::new();
+        let large = DocumentAttached {
+            body: "lorem ipsum ".repeat(1000),
+        };
+
+        store
+            .publish(stream_id.clone(), vec![large], None)
+            .await
+            .unwrap();
+
+        let mut events = inner
+            .read_stream::<DocumentAttached>(stream_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            events.next().await.unwrap(),
+            Some((
+                DocumentAttached {
+                    body: "lorem ipsum ".repeat(1000)
+                },
+                EventStreamVersion::new(0)
+            ))
+        );
+        assert_eq!(events.next().await.unwrap(), None);
+    }
+}
@@ -1,17 +1,105 @@
 use crate::delay::RetryDelay;
+use crate::dyn_event_store::RawEvent;
 use crate::error::Error;
+use crate::event_store::EventStreamVersion;
+use crate::sleep::{DefaultSleeper, Sleeper};
+use crate::snapshot::SnapshotStore;
+use futures::future::BoxFuture;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
 const MAX_RETRIES_LIMIT: u32 = 10;
 const MIN_DELAY_MS: u64 = 50;
 const MAX_DELAY_MS: u64 = 5000;
 
-#[derive(Debug, Clone)]
+pub(crate) type PostCommitHook = Arc<
+    dyn Fn(Vec<RawEvent>, EventStreamVersion) -> BoxFuture<'static, Result<(), Error>>
+        + Send
+        + Sync,
+>;
+
+pub(crate) type FailureCapture = Arc<dyn Fn(&Error, Option<serde_json::Value>) + Send + Sync>;
+
+/// When `execute` stops retrying a conflicting command: after a number of
+/// retries, or once the time since the first attempt reaches a budget. Either
+/// way the backoff between attempts is the configured `RetryDelay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryLimit {
+    Count(u32),
+    Duration(Duration),
+}
+
+impl RetryLimit {
+    /// Whether another attempt may start after `retries` retries, `elapsed` after
+    /// the first one. The first attempt is always allowed.
+    pub(crate) fn allows(&self, retries: u32, elapsed: Duration) -> bool {
+        match self {
+            RetryLimit::Count(max_retries) => retries <= *max_retries,
+            RetryLimit::Duration(limit) => retries == 0 || elapsed < *limit,
+        }
+    }
+
+    /// The retry count limit, or `None` when retries are limited by time.
+    pub fn max_retries(&self) -> Option<u32> {
+        match self {
+            RetryLimit::Count(max_retries) => Some(*max_retries),
+            RetryLimit::Duration(_) => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct ExecuteConfig {
-    max_retries: u32,
+    retry_limit: RetryLimit,
     retry_delay: RetryDelay,
+    sleeper: Arc<dyn Sleeper>,
+    snapshots: Option<SnapshotConfig>,
+    post_commit: Option<PostCommitHook>,
+    failure_capture: Option<FailureCapture>,
+    requires_leader: bool,
+    event_positions: bool,
+    duplicate_detection: bool,
+    handle_timeout: Option<Duration>,
+    allow_nil_stream_id: bool,
+}
+
+impl fmt::Debug for ExecuteConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecuteConfig")
+            .field("retry_limit", &self.retry_limit)
+            .field("retry_delay", &self.retry_delay)
+            .field("sleeper", &self.sleeper)
+            .field("snapshots", &self.snapshots)
+            .field("post_commit", &self.post_commit.as_ref().map(|_| "<hook>"))
+            .field(
+                "failure_capture",
+                &self.failure_capture.as_ref().map(|_| "<hook>"),
+            )
+            .field("requires_leader", &self.requires_leader)
+            .field("event_positions", &self.event_positions)
+            .field("duplicate_detection", &self.duplicate_detection)
+            .field("handle_timeout", &self.handle_timeout)
+            .field("allow_nil_stream_id", &self.allow_nil_stream_id)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SnapshotConfig {
+    pub(crate) store: Arc<dyn SnapshotStore>,
+    pub(crate) threshold: u64,
 }
 
 impl ExecuteConfig {
+    /// A builder with no retry defaults: `build` fails unless `max_retries`,
+    /// `base_delay` and `max_delay` were all set, so the policy is always chosen
+    /// deliberately.
+    pub fn strict() -> StrictExecuteConfig {
+        StrictExecuteConfig::default()
+    }
+
     pub fn with_max_retries(mut self, max_retries: u32) -> Result<Self, Error> {
         if max_retries == 0 {
             return Err(Error::InvalidConfig {
@@ -25,10 +113,27 @@ impl ExecuteConfig {
                 parameter: Some("max_retries".to_string()),
             });
         }
-        self.max_retries = max_retries;
+        self.retry_limit = RetryLimit::Count(max_retries);
         Ok(self)
     }
 
+    /// Replaces the retry count with `limit`. `RetryLimit::Duration` keeps
+    /// retrying while the next backoff would end before that long has passed since
+    /// the first attempt, so no retry sleeps past the limit.
+    pub fn with_retry_limit(self, limit: RetryLimit) -> Result<Self, Error> {
+        match limit {
+            RetryLimit::Count(max_retries) => self.with_max_retries(max_retries),
+            RetryLimit::Duration(duration) if duration.is_zero() => Err(Error::InvalidConfig {
+                message: "retry_duration cannot be 0".to_string(),
+                parameter: Some("retry_duration".to_string()),
+            }),
+            RetryLimit::Duration(_) => Ok(Self {
+                retry_limit: limit,
+                ..self
+            }),
+        }
+    }
+
     pub fn with_base_delay(mut self, delay_ms: u64) -> Result<Self, Error> {
         if delay_ms == 0 {
             return Err(Error::InvalidConfig {
@@ -49,7 +154,34 @@ impl ExecuteConfig {
             });
         }
         // Update retry delay config with new base delay but keep max delay
-        self.retry_delay = RetryDelay::new(delay_ms, self.retry_delay.max_delay_ms());
+        self.retry_delay = RetryDelay::new(delay_ms, self.retry_delay.max_delay_ms())
+            .with_jitter(self.retry_delay.has_jitter());
+        Ok(self)
+    }
+
+    /// Replaces exponential backoff with the same (jittered) delay before every
+    /// retry. A later `with_base_delay` or `with_max_delay` switches back.
+    pub fn with_constant_delay(mut self, delay_ms: u64) -> Result<Self, Error> {
+        if delay_ms == 0 {
+            return Err(Error::InvalidConfig {
+                message: "constant_delay_ms cannot be 0".to_string(),
+                parameter: Some("constant_delay_ms".to_string()),
+            });
+        }
+        if delay_ms < MIN_DELAY_MS {
+            return Err(Error::InvalidConfig {
+                message: format!("constant_delay_ms must be at least {MIN_DELAY_MS}ms"),
+                parameter: Some("constant_delay_ms".to_string()),
+            });
+        }
+        if delay_ms > MAX_DELAY_MS {
+            return Err(Error::InvalidConfig {
+                message: format!("constant_delay_ms cannot exceed {MAX_DELAY_MS}ms"),
+                parameter: Some("constant_delay_ms".to_string()),
+            });
+        }
+        self.retry_delay =
+            RetryDelay::constant(delay_ms).with_jitter(self.retry_delay.has_jitter());
         Ok(self)
     }
 
@@ -63,31 +195,243 @@ impl ExecuteConfig {
                 parameter: Some("max_delay_ms".to_string()),
             });
         }
-        self.retry_delay = RetryDelay::new(self.retry_delay.base_delay_ms(), max_delay_ms);
+        self.retry_delay = RetryDelay::new(self.retry_delay.base_delay_ms(), max_delay_ms)
+            .with_jitter(self.retry_delay.has_jitter());
+        Ok(self)
+    }
+
+    /// Turns retry jitter on or off (it is on by default). Without it, every run
+    /// waits exactly the capped backoff, for tests that assert on retry timing.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.retry_delay = self.retry_delay.with_jitter(jitter);
+        self
+    }
+
+    pub fn with_sleeper(mut self, sleeper: impl Sleeper + 'static) -> Self {
+        self.sleeper = Arc::new(sleeper);
+        self
+    }
+
+    /// Persists a snapshot after a successful append once at least `threshold`
+    /// events have been written since the snapshot the command was replayed from.
+    pub fn with_snapshots(
+        mut self,
+        store: impl SnapshotStore + 'static,
+        threshold: u64,
+    ) -> Result<Self, Error> {
+        if threshold == 0 {
+            return Err(Error::InvalidConfig {
+                message: "snapshot_threshold cannot be 0".to_string(),
+                parameter: Some("snapshot_threshold".to_string()),
+            });
+        }
+        self.snapshots = Some(SnapshotConfig {
+            store: Arc::new(store),
+            threshold,
+        });
         Ok(self)
     }
 
-    pub fn max_retries(&self) -> u32 {
-        self.max_retries
+    /// Runs `hook` after events are committed and before `execute` returns. An
+    /// error from the hook is returned from `execute`, but the events stay
+    /// committed, so forwarding through the hook is at-least-once: callers that
+    /// retry the command after a hook failure must tolerate seeing it again.
+    pub fn with_post_commit<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(Vec<RawEvent>, EventStreamVersion) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let hook: PostCommitHook = Arc::new(
+            move |events: Vec<RawEvent>,
+                  version: EventStreamVersion|
+                  -> BoxFuture<'static, Result<(), Error>> {
+                Box::pin(hook(events, version))
+            },
+        );
+        self.post_commit = Some(hook);
+        self
+    }
+
+    /// Calls `capture` when `execute` fails, with the error it is about to return and
+    /// the command's state as serialized by `AggregateState::to_snapshot`, for
+    /// logging or dead-lettering. It never runs on success and can't change the
+    /// error.
+    pub fn with_failure_capture<F>(mut self, capture: F) -> Self
+    where
+        F: Fn(&Error, Option<serde_json::Value>) + Send + Sync + 'static,
+    {
+        self.failure_capture = Some(Arc::new(capture));
+        self
+    }
+
+    /// Replays from the cluster leader so the version `execute` appends against is
+    /// never stale, avoiding conflicts caused only by follower lag.
+    pub fn with_requires_leader(mut self, requires_leader: bool) -> Self {
+        self.requires_leader = requires_leader;
+        self
+    }
+
+    /// Reports the global log position of every committed event in
+    /// `CommandOutcome::Committed`. The append only returns the position of the
+    /// whole write, so this reads the new events back from the leader. Stores that
+    /// don't record positions leave them empty. If the read fails, or doesn't find
+    /// the new events at the versions they were written at, the command fails with
+    /// `Error::PositionsUnavailable` even though its events are committed.
+    pub fn with_event_positions(mut self, event_positions: bool) -> Self {
+        self.event_positions = event_positions;
+        self
+    }
+
+    /// Rejects a command with `Error::DuplicateEvents` when the events `handle`
+    /// returns repeat the last events of the stream it was replayed from, which
+    /// usually means the state didn't apply them. Events are compared by their
+    /// serialized form against the last 64 events replayed, so longer batches,
+    /// events before a snapshot, and commands that skip replay aren't checked.
+    pub fn with_duplicate_detection(mut self, duplicate_detection: bool) -> Self {
+        self.duplicate_detection = duplicate_detection;
+        self
+    }
+
+    /// Bounds the command's own work, separately from any store deadline, failing
+    /// with `Error::HandleTimedOut` when it takes longer than `timeout`. `prepare`
+    /// is abandoned once the timeout passes; `handle` is synchronous and can't be
+    /// interrupted, so an overrun is detected when it returns and its events are
+    /// discarded. Without the `tokio` feature, `prepare` is timed on a thread of
+    /// its own for each command, which exits once `prepare` finishes.
+    pub fn with_handle_timeout(mut self, timeout: Duration) -> Result<Self, Error> {
+        if timeout.is_zero() {
+            return Err(Error::InvalidConfig {
+                message: "handle_timeout cannot be 0".to_string(),
+                parameter: Some("handle_timeout".to_string()),
+            });
+        }
+        self.handle_timeout = Some(timeout);
+        Ok(self)
+    }
+
+    /// Runs commands whose stream id is the nil UUID, which are rejected by default
+    /// with `Error::InvalidConfig` before the store is contacted. The store checks
+    /// the id too, so it must also be built with `with_nil_stream_id_allowed`.
+    pub fn with_nil_stream_id_allowed(mut self, allowed: bool) -> Self {
+        self.allow_nil_stream_id = allowed;
+        self
+    }
+
+    pub fn retry_limit(&self) -> RetryLimit {
+        self.retry_limit
+    }
+
+    /// The retry count limit, or `None` when retries are limited by time.
+    pub fn max_retries(&self) -> Option<u32> {
+        self.retry_limit.max_retries()
     }
 
     pub fn retry_delay(&self) -> &RetryDelay {
         &self.retry_delay
     }
+
+    pub fn sleeper(&self) -> &dyn Sleeper {
+        self.sleeper.as_ref()
+    }
+
+    pub fn requires_leader(&self) -> bool {
+        self.requires_leader
+    }
+
+    pub fn event_positions(&self) -> bool {
+        self.event_positions
+    }
+
+    pub fn duplicate_detection(&self) -> bool {
+        self.duplicate_detection
+    }
+
+    pub fn handle_timeout(&self) -> Option<Duration> {
+        self.handle_timeout
+    }
+
+    pub fn nil_stream_id_allowed(&self) -> bool {
+        self.allow_nil_stream_id
+    }
+
+    pub(crate) fn snapshots(&self) -> Option<&SnapshotConfig> {
+        self.snapshots.as_ref()
+    }
+
+    pub(crate) fn post_commit(&self) -> Option<&PostCommitHook> {
+        self.post_commit.as_ref()
+    }
+
+    pub(crate) fn failure_capture(&self) -> Option<&FailureCapture> {
+        self.failure_capture.as_ref()
+    }
 }
 
 impl Default for ExecuteConfig {
     fn default() -> Self {
         Self {
-            max_retries: 3,
+            retry_limit: RetryLimit::Count(3),
             retry_delay: RetryDelay::default(),
+            sleeper: Arc::new(DefaultSleeper::default()),
+            snapshots: None,
+            post_commit: None,
+            failure_capture: None,
+            requires_leader: false,
+            event_positions: false,
+            duplicate_detection: false,
+            handle_timeout: None,
+            allow_nil_stream_id: false,
         }
     }
 }
 
+/// Built by `ExecuteConfig::strict`. Each value is validated by `build` as by
+/// the matching `ExecuteConfig::with_*` method.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StrictExecuteConfig {
+    max_retries: Option<u32>,
+    base_delay_ms: Option<u64>,
+    max_delay_ms: Option<u64>,
+}
+
+impl StrictExecuteConfig {
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn base_delay(mut self, delay_ms: u64) -> Self {
+        self.base_delay_ms = Some(delay_ms);
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay_ms: u64) -> Self {
+        self.max_delay_ms = Some(max_delay_ms);
+        self
+    }
+
+    pub fn build(self) -> Result<ExecuteConfig, Error> {
+        let max_retries = required(self.max_retries, "max_retries")?;
+        let base_delay_ms = required(self.base_delay_ms, "base_retry_delay_ms")?;
+        let max_delay_ms = required(self.max_delay_ms, "max_delay_ms")?;
+        ExecuteConfig::default()
+            .with_max_retries(max_retries)?
+            .with_base_delay(base_delay_ms)?
+            .with_max_delay(max_delay_ms)
+    }
+}
+
+fn required<T>(value: Option<T>, parameter: &str) -> Result<T, Error> {
+    value.ok_or_else(|| Error::InvalidConfig {
+        message: format!("{parameter} must be set explicitly"),
+        parameter: Some(parameter.to_string()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::snapshot::InMemorySnapshotStore;
 
     #[test]
     fn validates_max_retries() {
@@ -117,7 +461,7 @@ mod tests {
         let config = ExecuteConfig::default()
             .with_max_retries(5)
             .expect("Failed to set valid max_retries");
-        assert_eq!(config.max_retries(), 5);
+        assert_eq!(config.max_retries(), Some(5));
     }
 
     #[test]
@@ -187,13 +531,162 @@ mod tests {
         assert_eq!(config.retry_delay().max_delay_ms(), 1000);
     }
 
+    #[test]
+    fn validates_constant_delay() {
+        match ExecuteConfig::default().with_constant_delay(0) {
+            Err(Error::InvalidConfig {
+                message, parameter, ..
+            }) => {
+                assert_eq!(message, "constant_delay_ms cannot be 0");
+                assert_eq!(parameter, Some("constant_delay_ms".to_string()));
+            }
+            other => panic!("Expected InvalidConfig error, got {:?}", other),
+        }
+
+        match ExecuteConfig::default().with_constant_delay(MAX_DELAY_MS + 1) {
+            Err(Error::InvalidConfig {
+                message, parameter, ..
+            }) => {
+                assert_eq!(
+                    message,
+                    format!("constant_delay_ms cannot exceed {MAX_DELAY_MS}ms")
+                );
+                assert_eq!(parameter, Some("constant_delay_ms".to_string()));
+            }
+            other => panic!("Expected InvalidConfig error, got {:?}", other),
+        }
+
+        let config = ExecuteConfig::default()
+            .with_constant_delay(200)
+            .expect("Failed to set valid constant_delay");
+        assert!(config.retry_delay().is_constant());
+        assert_eq!(config.retry_delay().base_delay_ms(), 200);
+    }
+
+    #[test]
+    fn validates_snapshot_threshold() {
+        match ExecuteConfig::default().with_snapshots(InMemorySnapshotStore::new(), 0) {
+            Err(Error::InvalidConfig {
+                message, parameter, ..
+            }) => {
+                assert_eq!(message, "snapshot_threshold cannot be 0");
+                assert_eq!(parameter, Some("snapshot_threshold".to_string()));
+            }
+            other => panic!("Expected InvalidConfig error, got {:?}", other),
+        }
+
+        let config = ExecuteConfig::default()
+            .with_snapshots(InMemorySnapshotStore::new(), 10)
+            .expect("Failed to set valid snapshot threshold");
+        assert_eq!(config.snapshots().map(|s| s.threshold), Some(10));
+    }
+
+    #[test]
+    fn validates_handle_timeout() {
+        match ExecuteConfig::default().with_handle_timeout(Duration::ZERO) {
+            Err(Error::InvalidConfig {
+                message, parameter, ..
+            }) => {
+                assert_eq!(message, "handle_timeout cannot be 0");
+                assert_eq!(parameter, Some("handle_timeout".to_string()));
+            }
+            other => panic!("Expected InvalidConfig error, got {:?}", other),
+        }
+
+        let config = ExecuteConfig::default()
+            .with_handle_timeout(Duration::from_secs(1))
+            .expect("Failed to set valid handle timeout");
+        assert_eq!(config.handle_timeout(), Some(Duration::from_secs(1)));
+        assert_eq!(ExecuteConfig::default().handle_timeout(), None);
+    }
+
+    #[test]
+    fn jitter_survives_later_delay_changes() {
+        let config = ExecuteConfig::default()
+            .with_jitter(false)
+            .with_base_delay(200)
+            .unwrap()
+            .with_max_delay(1000)
+            .unwrap();
+        assert!(!config.retry_delay().has_jitter());
+        assert_eq!(
+            config.retry_delay().calculate_delay(1),
+            std::time::Duration::from_millis(400)
+        );
+    }
+
+    #[test]
+    fn strict_configs_require_every_retry_setting() {
+        match ExecuteConfig::strict()
+            .max_retries(5)
+            .base_delay(100)
+            .build()
+        {
+            Err(Error::InvalidConfig {
+                message, parameter, ..
+            }) => {
+                assert_eq!(message, "max_delay_ms must be set explicitly");
+                assert_eq!(parameter, Some("max_delay_ms".to_string()));
+            }
+            other => panic!("Expected InvalidConfig error, got {:?}", other),
+        }
+
+        assert!(
+            ExecuteConfig::strict()
+                .max_retries(0)
+                .base_delay(100)
+                .max_delay(1000)
+                .build()
+                .is_err()
+        );
+
+        let config = ExecuteConfig::strict()
+            .max_retries(5)
+            .base_delay(200)
+            .max_delay(1000)
+            .build()
+            .expect("Failed to build a complete strict config");
+        assert_eq!(config.max_retries(), Some(5));
+        assert_eq!(config.retry_delay().base_delay_ms(), 200);
+        assert_eq!(config.retry_delay().max_delay_ms(), 1000);
+    }
+
+    #[test]
+    fn validates_retry_limit() {
+        match ExecuteConfig::default().with_retry_limit(RetryLimit::Duration(Duration::ZERO)) {
+            Err(Error::InvalidConfig { parameter, .. }) => {
+                assert_eq!(parameter, Some("retry_duration".to_string()));
+            }
+            other => panic!("Expected InvalidConfig error, got: {:?}", other),
+        }
+        assert!(
+            ExecuteConfig::default()
+                .with_retry_limit(RetryLimit::Count(0))
+                .is_err()
+        );
+
+        let limit = RetryLimit::Duration(Duration::from_secs(2));
+        let config = ExecuteConfig::default()
+            .with_retry_limit(limit)
+            .expect("Failed to set a duration limit");
+        assert_eq!(config.retry_limit(), limit);
+        assert_eq!(config.max_retries(), None);
+        assert_eq!(limit.max_retries(), None);
+        assert_eq!(RetryLimit::Count(5).max_retries(), Some(5));
+
+        let config = config
+            .with_max_retries(5)
+            .expect("Failed to set max_retries");
+        assert_eq!(config.retry_limit(), RetryLimit::Count(5));
+    }
+
     #[test]
     fn default_values_are_valid() {
         let config = ExecuteConfig::default();
         assert!(
             config
                 .clone()
-                .with_max_retries(config.max_retries())
+                .with_retry_limit(config.retry_limit())
                 .is_ok()
         );
         assert!(
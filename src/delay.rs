@@ -1,6 +1,6 @@
 use rand::prelude::*;
 use std::cell::RefCell;
-use tokio::time::Duration;
+use std::time::Duration;
 
 thread_local! {
     static THREAD_RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(0));
@@ -10,6 +10,8 @@ thread_local! {
 pub struct RetryDelay {
     base_delay_ms: u64,
     max_delay_ms: u64,
+    constant: bool,
+    jitter: bool,
 }
 
 impl RetryDelay {
@@ -17,9 +19,29 @@ impl RetryDelay {
         Self {
             base_delay_ms,
             max_delay_ms,
+            constant: false,
+            jitter: true,
         }
     }
 
+    /// Waits (up to) `delay_ms` before every retry, however many have happened.
+    pub fn constant(delay_ms: u64) -> Self {
+        Self {
+            base_delay_ms: delay_ms,
+            max_delay_ms: delay_ms,
+            constant: true,
+            jitter: true,
+        }
+    }
+
+    /// Without jitter, `calculate_delay` always returns the full capped backoff,
+    /// which makes retry timing reproducible in tests. Jitter is on by default so
+    /// that conflicting writers spread out.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
     pub fn base_delay_ms(&self) -> u64 {
         self.base_delay_ms
     }
@@ -28,12 +50,32 @@ impl RetryDelay {
         self.max_delay_ms
     }
 
-    pub fn calculate_delay(&self, retry_count: u32) -> Duration {
-        // Calculate exponential delay
-        let exp_delay = self.base_delay_ms * 2u64.pow(retry_count);
+    pub fn is_constant(&self) -> bool {
+        self.constant
+    }
 
-        // Cap at max delay
-        let capped_delay = exp_delay.min(self.max_delay_ms);
+    pub fn has_jitter(&self) -> bool {
+        self.jitter
+    }
+
+    /// The longest `calculate_delay(retry_count)` can return: the capped backoff
+    /// before jitter.
+    pub fn max_possible_delay(&self, retry_count: u32) -> Duration {
+        Duration::from_millis(self.capped_delay_ms(retry_count))
+    }
+
+    /// The longest total time spent waiting across `max_retries` retries.
+    pub fn max_total_delay(&self, max_retries: u32) -> Duration {
+        (0..max_retries)
+            .map(|retry| self.max_possible_delay(retry))
+            .sum()
+    }
+
+    pub fn calculate_delay(&self, retry_count: u32) -> Duration {
+        let capped_delay = self.capped_delay_ms(retry_count);
+        if !self.jitter {
+            return Duration::from_millis(capped_delay);
+        }
 
         // Apply full jitter using thread-local RNG
         let jittered_delay = THREAD_RNG.with(|rng| {
@@ -43,6 +85,21 @@ impl RetryDelay {
 
         Duration::from_millis(jittered_delay)
     }
+
+    fn capped_delay_ms(&self, retry_count: u32) -> u64 {
+        if self.constant {
+            return self.base_delay_ms;
+        }
+
+        // Calculate exponential delay, saturating rather than overflowing
+        let exp_delay = 2u64
+            .checked_pow(retry_count)
+            .and_then(|factor| self.base_delay_ms.checked_mul(factor))
+            .unwrap_or(u64::MAX);
+
+        // Cap at max delay
+        exp_delay.min(self.max_delay_ms)
+    }
 }
 
 impl Default for RetryDelay {
@@ -50,6 +107,8 @@ impl Default for RetryDelay {
         Self {
             base_delay_ms: 100,
             max_delay_ms: 30_000, // 30 seconds max delay
+            constant: false,
+            jitter: true,
         }
     }
 }
@@ -59,6 +118,20 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn delays_without_jitter_are_deterministic() {
+        let retry_delay = RetryDelay::new(100, 1000).with_jitter(false);
+
+        for _ in 0..100 {
+            assert_eq!(retry_delay.calculate_delay(0), Duration::from_millis(100));
+            assert_eq!(retry_delay.calculate_delay(2), Duration::from_millis(400));
+            assert_eq!(retry_delay.calculate_delay(5), Duration::from_millis(1000));
+        }
+
+        let constant = RetryDelay::constant(250).with_jitter(false);
+        assert_eq!(constant.calculate_delay(7), Duration::from_millis(250));
+    }
+
     #[test]
     fn calculates_delay_within_bounds() {
         let retry_delay = RetryDelay::new(100, 1000);
@@ -126,4 +199,54 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn large_retry_counts_saturate_at_max_delay() {
+        let retry_delay = RetryDelay::new(100, 500);
+
+        assert_eq!(
+            retry_delay.max_possible_delay(u32::MAX),
+            Duration::from_millis(500)
+        );
+        assert!(retry_delay.calculate_delay(200).as_millis() <= 500);
+    }
+
+    #[test]
+    fn constant_delay_ignores_retry_count() {
+        let retry_delay = RetryDelay::constant(100);
+
+        for retry in 0..10 {
+            let delay = retry_delay.calculate_delay(retry);
+            assert!(
+                delay.as_millis() <= 100,
+                "Constant delay should not grow with retries"
+            );
+        }
+    }
+
+    #[test]
+    fn previews_the_backoff_envelope() {
+        let retry_delay = RetryDelay::new(100, 500);
+
+        assert_eq!(
+            retry_delay.max_possible_delay(0),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            retry_delay.max_possible_delay(2),
+            Duration::from_millis(400)
+        );
+        assert_eq!(
+            retry_delay.max_possible_delay(3),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            retry_delay.max_total_delay(4),
+            Duration::from_millis(100 + 200 + 400 + 500)
+        );
+        assert_eq!(retry_delay.max_total_delay(0), Duration::ZERO);
+
+        let constant = RetryDelay::constant(100);
+        assert_eq!(constant.max_total_delay(3), Duration::from_millis(300));
+    }
 }
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use uuid::Uuid;
+
+use crate::event::serialize_event;
+use crate::{Error, Event, EventStore, EventStream, EventStreamId, EventStreamVersion};
+
+pub type RawEventStream = BoxStream<'static, Result<(RawEvent, EventStreamVersion), Error>>;
+
+/// An event that has already been serialized, independent of any `Event` type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawEvent {
+    pub event_type: String,
+    pub content_type: String,
+    pub data: Bytes,
+    /// Metadata stored alongside the event, written as-is by stores that support it.
+    pub metadata: Option<Bytes>,
+    pub id: Option<Uuid>,
+}
+
+impl RawEvent {
+    pub fn from_event<E: Event>(event: &E) -> Result<Self, Error> {
+        Self::from_event_at(event, 0)
+    }
+
+    /// Serializes the event at `index` of a batch, so a failure names its position.
+    pub(crate) fn from_event_at<E: Event>(event: &E, index: usize) -> Result<Self, Error> {
+        Ok(Self {
+            event_type: event.event_type(),
+            content_type: event.content_type().to_string(),
+            data: Bytes::from(serialize_event(event, index)?),
+            metadata: None,
+            id: None,
+        })
+    }
+
+    pub fn to_event<E: Event>(&self) -> Result<E, Error> {
+        Ok(serde_json::from_slice(&self.data)?)
+    }
+
+    /// The payload as untyped JSON, for events of types with no `Event` impl.
+    pub fn to_json(&self) -> Result<serde_json::Value, Error> {
+        Ok(serde_json::from_slice(&self.data)?)
+    }
+}
+
+/// An object-safe counterpart to [`EventStore`], so stores can be chosen at runtime
+/// and held as `Arc<dyn DynEventStore>`. That `Arc` implements `EventStore` itself and
+/// can be passed straight to `execute`.
+pub trait DynEventStore: Send + Sync {
+    fn publish_raw(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+
+    fn read_raw(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<RawEventStream, Error>>;
+}
+
+impl EventStore for Arc<dyn DynEventStore> {
+    async fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| RawEvent::from_event_at(event, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.publish_raw(stream_id, events, expected_version).await
+    }
+
+    async fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(index, (id, event))| {
+                RawEvent::from_event_at(event, index).map(|raw| RawEvent {
+                    id: Some(*id),
+                    ..raw
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.publish_raw(stream_id, events, expected_version).await
+    }
+
+    async fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        _events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.publish_raw(stream_id, serialized, expected_version)
+            .await
+    }
+
+    async fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<EventStream<E>, Error> {
+        let stream_name = stream_id.to_string();
+        let events = self.read_raw(stream_id).await?;
+        Ok(EventStream::from_raw(events, stream_name))
+    }
+}
@@ -1,48 +1,206 @@
+#[cfg(feature = "kurrent")]
 use eventstore::ClientSettingsParseError;
 use std::fmt::Debug;
 use thiserror::Error;
 
-use crate::event_store::{EventStreamId, EventStreamVersion};
+use crate::config::RetryLimit;
+use crate::event_store::{EventStreamId, EventStreamVersion, ExpectedVersion};
 
+/// New variants are added as the crate grows, so match on the classification
+/// helpers (`is_conflict`, `is_retryable`, `code`) rather than on every variant.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
+    #[cfg(feature = "kurrent")]
     #[error(transparent)]
     EventStoreSettings(#[from] ClientSettingsParseError),
 
     #[error(transparent)]
     EventDeserializationError(#[from] serde_json::error::Error),
 
+    #[error("Failed to serialize event '{event_type}' at index {index} of the batch")]
+    EventSerializationFailed {
+        event_type: String,
+        index: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Event '{event_type}' failed validation: {message}")]
+    EventValidationFailed { event_type: String, message: String },
+
+    #[error(
+        "Failed to deserialize event '{event_type}' at version {version:?} of stream '{stream}'"
+    )]
+    EventDeserializationFailed {
+        stream: String,
+        version: EventStreamVersion,
+        event_type: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to compress or decompress an event payload")]
+    CompressionFailed {
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Unexpected event type '{event_type}' on stream '{stream}'")]
+    UnexpectedEventType { stream: String, event_type: String },
+
     #[error("Stream not found: {stream_id}", stream_id = .0.to_string())]
     EventStoreStreamNotFound(EventStreamId),
 
-    #[error("Version mismatch for stream '{stream:?}': {:?}", match (&expected, &actual) {
-        (Some(e), Some(a)) => format!("expected version {:?}, but stream is at version {:?}", e, a),
-        (Some(e), None) => format!("expected version {:?}, but stream does not exist", e),
-        (None, Some(a)) => format!("stream exists at version {:?}, but no version was expected", a),
-        (None, None) => "invalid version state".to_string()
-    })]
+    #[error(
+        "Version mismatch for stream '{stream:?}': {}",
+        describe_mismatch(expected, actual)
+    )]
     EventStoreVersionMismatch {
         stream: EventStreamId,
-        expected: Option<EventStreamVersion>,
+        expected: ExpectedVersion,
         actual: Option<EventStreamVersion>,
+        #[cfg(feature = "kurrent")]
         #[source]
         source: eventstore::Error,
     },
 
+    #[error(
+        "Event '{event_type}' belongs to stream '{actual}' but was returned by a command for stream '{expected}'"
+    )]
+    EventStreamMismatch {
+        expected: EventStreamId,
+        actual: EventStreamId,
+        event_type: String,
+    },
+
+    #[error("Stream '{stream}' has been deleted")]
+    StreamDeleted { stream: String },
+
+    #[error("Operation on stream '{stream}' exceeded its deadline")]
+    DeadlineExceeded { stream: String },
+
+    /// The events were appended, but reads didn't see them within `timeout`.
+    /// Publishing them again would duplicate them.
+    #[error(
+        "Stream '{stream}' was written up to version {version:?}, but reads did not observe it within {timeout:?}"
+    )]
+    WriteNotObserved {
+        stream: String,
+        version: EventStreamVersion,
+        timeout: std::time::Duration,
+    },
+
+    /// The events were committed up to `version`, but reading back their log
+    /// positions for `ExecuteConfig::with_event_positions` failed. Executing the
+    /// command again would commit them twice.
+    #[error(
+        "Stream '{stream}' was written up to version {version:?}, but the events' positions could not be read back: {reason}"
+    )]
+    PositionsUnavailable {
+        stream: String,
+        version: EventStreamVersion,
+        reason: String,
+        #[source]
+        source: Option<Box<Error>>,
+    },
+
+    #[error("Cannot truncate stream '{stream}' before version {requested:?}: {}", match head {
+        Some(h) => format!("stream is only at version {:?}", h),
+        None => "stream does not exist".to_string()
+    })]
+    TruncateBeyondHead {
+        stream: EventStreamId,
+        requested: EventStreamVersion,
+        head: Option<EventStreamVersion>,
+    },
+
+    #[cfg(feature = "kurrent")]
     #[error(transparent)]
     EventStoreOther(#[from] eventstore::Error),
 
-    #[error("Command failed (attempt {attempt} of {max_attempts}): {message}")]
+    /// `Kurrent::connect` couldn't reach the server, or the check timed out.
+    #[cfg(feature = "kurrent")]
+    #[error("Failed to connect to the event store")]
+    ConnectionFailed {
+        #[source]
+        source: eventstore::Error,
+    },
+
+    /// `Kurrent::server_info` couldn't query the server's version and features.
+    #[cfg(feature = "kurrent")]
+    #[error("Failed to read the server's version and features: {reason}")]
+    ServerInfoUnavailable {
+        reason: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    /// A read that failed part-way through replaying a stream for `execute`.
+    /// Failures before the first event arrives are returned unwrapped.
+    #[error(
+        "Replay of stream '{stream}' failed after applying {events_applied} events, at version {last_version:?}"
+    )]
+    ReplayFailed {
+        stream: String,
+        events_applied: usize,
+        last_version: EventStreamVersion,
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// `max_attempts` is `None` when retries are limited by time.
+    #[error("Command failed (attempt {attempt}{}): {message}", max_attempts.map(|max| format!(" of {max}")).unwrap_or_default())]
     CommandFailed {
         message: String,
         attempt: u32,
-        max_attempts: u32,
+        max_attempts: Option<u32>,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// `handle` returned the same `count` events the stream already ends with, at
+    /// `version`. Only raised with `ExecuteConfig::with_duplicate_detection`.
+    #[error(
+        "Command on stream '{stream}' repeated the last {count} events, ending at version {version:?}"
+    )]
+    DuplicateEvents {
+        stream: String,
+        version: EventStreamVersion,
+        count: usize,
+    },
+
+    /// The command's `prepare` or `handle` ran past
+    /// `ExecuteConfig::with_handle_timeout`.
+    #[error("Command on stream '{stream}' timed out after {elapsed:?}")]
+    HandleTimedOut {
+        stream: String,
+        elapsed: std::time::Duration,
+    },
+
+    #[error("Command on stream '{stream}' was abandoned: {reason}")]
+    CommandAbandoned {
+        stream: String,
+        reason: String,
         #[source]
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
-    #[error("Command execution exceeded maximum retries ({max_retries}) for stream '{stream}'")]
-    MaxRetriesExceeded { stream: String, max_retries: u32 },
+    /// `limit` is the limit that stopped the command after `retries` retries; a
+    /// `RetryBudget` that ran dry shows as a count of the retries it allowed.
+    /// `per_attempt_delays` holds the backoff slept before each retry and
+    /// `total_backoff` their sum.
+    #[error("Command execution exceeded {} for stream '{stream}'", exceeded_limit(.limit, .retries))]
+    MaxRetriesExceeded {
+        stream: String,
+        limit: RetryLimit,
+        retries: u32,
+        total_backoff: std::time::Duration,
+        per_attempt_delays: Vec<std::time::Duration>,
+    },
 
     #[error("Invalid configuration{}: {message}", parameter.as_ref().map(|p| format!(" parameter '{p}'")).unwrap_or_default())]
     InvalidConfig {
@@ -50,3 +208,128 @@ pub enum Error {
         parameter: Option<String>,
     },
 }
+
+impl Error {
+    /// Whether the stream changed since it was read, i.e. an optimistic concurrency
+    /// conflict.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Error::EventStoreVersionMismatch { .. })
+    }
+
+    /// Whether running the same operation again later may succeed: conflicts,
+    /// exhausted retries under contention, timeouts and lost connections.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::EventStoreVersionMismatch { .. }
+            | Error::MaxRetriesExceeded { .. }
+            | Error::DeadlineExceeded { .. } => true,
+            #[cfg(feature = "kurrent")]
+            Error::ConnectionFailed { .. } => true,
+            #[cfg(feature = "kurrent")]
+            Error::EventStoreOther(
+                eventstore::Error::GrpcConnectionError(_)
+                | eventstore::Error::NotLeaderException(_)
+                | eventstore::Error::DeadlineExceeded,
+            ) => true,
+            Error::ReplayFailed { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// The command's own error behind a `CommandFailed`, if it is an `E`. `None` for
+    /// every other variant.
+    pub fn command_error<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Error::CommandFailed { source, .. } => source.downcast_ref::<E>(),
+            _ => None,
+        }
+    }
+
+    /// A stable, machine-readable name for the kind of error, for logs and metrics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "kurrent")]
+            Error::EventStoreSettings(_) => "invalid_settings",
+            Error::EventDeserializationError(_) => "deserialization_failed",
+            Error::EventSerializationFailed { .. } => "serialization_failed",
+            Error::EventValidationFailed { .. } => "validation_failed",
+            Error::EventDeserializationFailed { .. } => "deserialization_failed",
+            Error::Io(_) => "io",
+            Error::CompressionFailed { .. } => "compression_failed",
+            Error::UnexpectedEventType { .. } => "unexpected_event_type",
+            Error::EventStoreStreamNotFound(_) => "stream_not_found",
+            Error::EventStoreVersionMismatch { .. } => "version_mismatch",
+            Error::EventStreamMismatch { .. } => "event_stream_mismatch",
+            Error::StreamDeleted { .. } => "stream_deleted",
+            Error::DeadlineExceeded { .. } => "deadline_exceeded",
+            Error::WriteNotObserved { .. } => "write_not_observed",
+            Error::PositionsUnavailable { .. } => "positions_unavailable",
+            Error::TruncateBeyondHead { .. } => "truncate_beyond_head",
+            #[cfg(feature = "kurrent")]
+            Error::EventStoreOther(_) => "event_store",
+            #[cfg(feature = "kurrent")]
+            Error::ConnectionFailed { .. } => "connection_failed",
+            #[cfg(feature = "kurrent")]
+            Error::ServerInfoUnavailable { .. } => "server_info_unavailable",
+            Error::ReplayFailed { .. } => "replay_failed",
+            Error::CommandFailed { .. } => "command_failed",
+            Error::DuplicateEvents { .. } => "duplicate_events",
+            Error::HandleTimedOut { .. } => "handle_timed_out",
+            Error::CommandAbandoned { .. } => "command_abandoned",
+            Error::MaxRetriesExceeded { .. } => "max_retries_exceeded",
+            Error::InvalidConfig { .. } => "invalid_config",
+        }
+    }
+}
+
+fn exceeded_limit(limit: &RetryLimit, retries: &u32) -> String {
+    match limit {
+        RetryLimit::Count(max_retries) => format!("maximum retries ({max_retries})"),
+        RetryLimit::Duration(limit) => {
+            format!("its retry time limit ({limit:?}) after {retries} retries")
+        }
+    }
+}
+
+/// An `EventStoreVersionMismatch` raised by mneme itself rather than the server,
+/// carrying the equivalent server error as its source.
+pub(crate) fn version_mismatch(
+    stream: EventStreamId,
+    expected: ExpectedVersion,
+    actual: Option<EventStreamVersion>,
+) -> Error {
+    Error::EventStoreVersionMismatch {
+        stream,
+        expected,
+        actual,
+        #[cfg(feature = "kurrent")]
+        source: eventstore::Error::WrongExpectedVersion {
+            expected: match expected {
+                ExpectedVersion::Any => eventstore::ExpectedRevision::Any,
+                ExpectedVersion::NoStream => eventstore::ExpectedRevision::NoStream,
+                ExpectedVersion::StreamExists => eventstore::ExpectedRevision::StreamExists,
+                ExpectedVersion::Exact(v) => eventstore::ExpectedRevision::Exact(v.value()),
+            },
+            current: match actual {
+                Some(v) => eventstore::CurrentRevision::Current(v.value()),
+                None => eventstore::CurrentRevision::NoStream,
+            },
+        },
+    }
+}
+
+fn describe_mismatch(expected: &ExpectedVersion, actual: &Option<EventStreamVersion>) -> String {
+    let expected = match expected {
+        ExpectedVersion::Any => "any version".to_string(),
+        ExpectedVersion::NoStream => "stream to not exist".to_string(),
+        ExpectedVersion::StreamExists => "stream to exist".to_string(),
+        ExpectedVersion::Exact(version) => format!("version {}", version.value()),
+    };
+    match actual {
+        Some(version) => format!(
+            "expected {expected}, but it is at version {}",
+            version.value()
+        ),
+        None => format!("expected {expected}, but it does not exist"),
+    }
+}
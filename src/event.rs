@@ -1,13 +1,94 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+use crate::error::Error;
+use crate::event_store::EventStreamId;
+
 pub trait Event: Debug + for<'de> Deserialize<'de> + Serialize + Send + Sync + Sized {
     fn event_type(&self) -> String;
+
+    /// The content type recorded with the event. The body is always serialized as
+    /// JSON; other values are for consumers that route on content type.
+    fn content_type(&self) -> &str {
+        "application/json"
+    }
+
+    /// Every `event_type` this type knows how to deserialize. When non-empty, reads
+    /// reject recorded events of any other type before attempting deserialization.
+    fn all_event_types() -> Vec<String> {
+        vec![]
+    }
+
+    /// Each variant's name paired with the event type it is stored under, as
+    /// generated by `#[derive(Event)]`. Stored event types are a contract with
+    /// every event already written, so tests can pin them with
+    /// `testing::assert_event_type_stability`. Empty by default.
+    fn event_type_table() -> Vec<(&'static str, &'static str)> {
+        vec![]
+    }
+
+    /// The stream this event belongs to, for events that carry their aggregate's id.
+    /// When `Some`, `execute` rejects the event unless it matches the command's
+    /// stream.
+    fn stream_id(&self) -> Option<EventStreamId> {
+        None
+    }
+
+    /// Checks invariants serde can't express before the event is written. An
+    /// error fails the publish with `Error::EventValidationFailed`, and nothing
+    /// from the batch is appended.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// When the event originally happened, for events imported from another
+    /// system. `Kurrent::import` records it in the event's metadata, since the
+    /// server always stamps events with the time they were appended.
+    fn occurred_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+/// The metadata key `Kurrent::import` records an event's original timestamp under.
+pub(crate) const ORIGINAL_TIMESTAMP_METADATA_KEY: &str = "original_timestamp";
+
+/// The original timestamp recorded in an event's metadata, if any.
+pub(crate) fn original_timestamp(metadata: &[u8]) -> Option<DateTime<Utc>> {
+    let metadata = serde_json::from_slice::<serde_json::Value>(metadata).ok()?;
+    let timestamp = metadata.get(ORIGINAL_TIMESTAMP_METADATA_KEY)?.as_str()?;
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Runs `Event::validate`, naming the event type in any error.
+pub(crate) fn validate_event<E: Event>(event: &E) -> Result<(), Error> {
+    event
+        .validate()
+        .map_err(|message| Error::EventValidationFailed {
+            event_type: event.event_type(),
+            message,
+        })
+}
+
+/// Validates and serializes the event at `index` of a batch, naming both in any
+/// serialization error.
+pub(crate) fn serialize_event<E: Event>(event: &E, index: usize) -> Result<Vec<u8>, Error> {
+    validate_event(event)?;
+    serde_json::to_vec(event).map_err(|source| Error::EventSerializationFailed {
+        event_type: event.event_type(),
+        index,
+        source,
+    })
 }
 
 impl Event for () {
     fn event_type(&self) -> String {
         "None".to_string()
     }
-}
 
+    fn all_event_types() -> Vec<String> {
+        vec!["None".to_string()]
+    }
+}
@@ -1,32 +1,431 @@
+use std::marker::PhantomData;
+use std::ops::Add;
+
+use futures::future::Either;
 use uuid::Uuid;
 
-use crate::{Error, Event, EventStream};
+use crate::error::version_mismatch;
+use crate::{Error, Event, EventStream, RawEvent};
 
+/// Every method takes `&self`, so one handle can serve concurrent reads and
+/// writes; stores that keep state between calls synchronize it themselves.
 pub trait EventStore {
+    /// Appends `events`, failing with `Error::EventStoreVersionMismatch` unless the
+    /// stream is at `expected_version`. `None` appends at any version, so nothing
+    /// guards against concurrent writers (see `WriteMode` to change this for `Kurrent`).
     fn publish<E: Event>(
-        &mut self,
+        &self,
         stream_id: EventStreamId,
         events: Vec<E>,
         expected_version: Option<EventStreamVersion>,
     ) -> impl std::future::Future<Output = Result<(), Error>> + Send;
 
+    /// Publishes events under caller-chosen ids. Stores without a notion of event
+    /// ids fall back to a plain `publish`.
+    fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        self.publish(
+            stream_id,
+            events.into_iter().map(|(_, event)| event).collect(),
+            expected_version,
+        )
+    }
+
+    /// Publishes events that start a new stream, failing with
+    /// `Error::EventStoreVersionMismatch` (expecting `ExpectedVersion::NoStream`) if
+    /// it already has events. The default checks with a read before publishing, so
+    /// it can't detect a stream created in between; stores that can make the check
+    /// part of the append override it.
+    fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut stream = self.read_stream::<E>(stream_id.clone()).await?;
+            let mut head = None;
+            while let Some((_, version)) = stream.next().await? {
+                head = Some(version);
+            }
+            if head.is_some() {
+                return Err(version_mismatch(stream_id, ExpectedVersion::NoStream, head));
+            }
+            self.publish(stream_id, events, None).await
+        }
+    }
+
+    /// Publishes `events` that `execute` has already serialized as `serialized`,
+    /// so stores that can append serialized events write those bytes rather than
+    /// serializing the batch again. The default publishes `events`, under the
+    /// serialized ids when every event has one.
+    fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        match serialized
+            .iter()
+            .map(|event| event.id)
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(ids) if !ids.is_empty() => Either::Left(self.publish_with_ids(
+                stream_id,
+                ids.into_iter().zip(events).collect(),
+                expected_version,
+            )),
+            _ => Either::Right(self.publish(stream_id, events, expected_version)),
+        }
+    }
+
+    /// `publish_new` for events already serialized as `serialized`. The default
+    /// publishes `events` with `publish_new`.
+    fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        _serialized: Vec<RawEvent>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send
+    where
+        Self: Sync,
+    {
+        self.publish_new(stream_id, events)
+    }
+
+    /// Reading a stream that doesn't exist yields an empty stream rather than an
+    /// error; `execute` relies on this to treat new streams as having no history.
     fn read_stream<E: Event>(
         &self,
         stream_id: EventStreamId,
     ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send;
+
+    /// Reads only the events recorded after `after`. Resuming at or beyond the head
+    /// yields an empty stream. The default reads from the start and skips ahead.
+    fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        let stream = self.read_stream(stream_id);
+        async move { Ok(stream.await?.skip_through(after)) }
+    }
+
+    /// Reads with the given options. Stores without a notion of leaders ignore
+    /// `requires_leader`.
+    fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        let after = options.after();
+        let stream = self.read_stream(stream_id);
+        async move {
+            let stream = stream.await?;
+            Ok(match after {
+                Some(after) => stream.skip_through(after),
+                None => stream,
+            })
+        }
+    }
+
+    /// The version of the stream's last event, or `None` if it has none. `E` is the
+    /// stream's event type: the default reads the stream through it, so stores that
+    /// can probe the head without reading events override it.
+    fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> impl std::future::Future<Output = Result<Option<EventStreamVersion>, Error>> + Send {
+        let stream = self.read_stream_with::<E>(stream_id, options);
+        async move {
+            let mut stream = stream.await?;
+            if let Some(head) = stream.head_version() {
+                return Ok(Some(head));
+            }
+            let mut head = None;
+            while let Some((_, version)) = stream.next().await? {
+                head = Some(version);
+            }
+            Ok(head)
+        }
+    }
+
+    /// Publishes to a stream bound to its event type, so events of another type
+    /// can't be written to it.
+    fn publish_typed<E: Event>(
+        &self,
+        stream: &TypedStream<E>,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        self.publish(stream.id().clone(), events, expected_version)
+    }
+
+    /// Reads a stream bound to its event type, so it can't be decoded as another.
+    fn read_typed<E: Event>(
+        &self,
+        stream: &TypedStream<E>,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        self.read_stream(stream.id().clone())
+    }
+}
+
+/// Lets a borrowed store stand in for the store itself, e.g. when a wrapper holds
+/// `&mut S` or a `Box<S>`.
+impl<T: EventStore + Sync> EventStore for &mut T {
+    fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        (**self).publish(stream_id, events, expected_version)
+    }
+
+    fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        (**self).publish_with_ids(stream_id, events, expected_version)
+    }
+
+    fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send
+    where
+        Self: Sync,
+    {
+        (**self).publish_new(stream_id, events)
+    }
+
+    fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        (**self).publish_serialized(stream_id, events, serialized, expected_version)
+    }
+
+    fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send
+    where
+        Self: Sync,
+    {
+        (**self).publish_new_serialized(stream_id, events, serialized)
+    }
+
+    fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        (**self).read_stream(stream_id)
+    }
+
+    fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        (**self).read_stream_from(stream_id, after)
+    }
+
+    fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        (**self).read_stream_with(stream_id, options)
+    }
+
+    fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> impl std::future::Future<Output = Result<Option<EventStreamVersion>, Error>> + Send {
+        (**self).head_version::<E>(stream_id, options)
+    }
+}
+
+impl<T: EventStore + Sync> EventStore for Box<T> {
+    fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        (**self).publish(stream_id, events, expected_version)
+    }
+
+    fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        (**self).publish_with_ids(stream_id, events, expected_version)
+    }
+
+    fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send
+    where
+        Self: Sync,
+    {
+        (**self).publish_new(stream_id, events)
+    }
+
+    fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        (**self).publish_serialized(stream_id, events, serialized, expected_version)
+    }
+
+    fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send
+    where
+        Self: Sync,
+    {
+        (**self).publish_new_serialized(stream_id, events, serialized)
+    }
+
+    fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        (**self).read_stream(stream_id)
+    }
+
+    fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        (**self).read_stream_from(stream_id, after)
+    }
+
+    fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> impl std::future::Future<Output = Result<EventStream<E>, Error>> + Send {
+        (**self).read_stream_with(stream_id, options)
+    }
+
+    fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> impl std::future::Future<Output = Result<Option<EventStreamVersion>, Error>> + Send {
+        (**self).head_version::<E>(stream_id, options)
+    }
+}
+
+/// The result of a read that tells a stream that never existed apart from one
+/// whose events are all truncated or otherwise hidden.
+pub enum ReadOutcome<E: Event> {
+    NotFound,
+    /// The stream exists but has no readable events. `head_version` is the last
+    /// version written, when the store can tell.
+    Empty {
+        head_version: Option<EventStreamVersion>,
+    },
+    Stream(EventStream<E>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    after: Option<EventStreamVersion>,
+    requires_leader: bool,
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn after(&self) -> Option<EventStreamVersion> {
+        self.after
+    }
+
+    pub fn requires_leader(&self) -> bool {
+        self.requires_leader
+    }
+
+    pub fn starting_after(mut self, version: EventStreamVersion) -> Self {
+        self.after = Some(version);
+        self
+    }
+
+    /// Only read from the cluster leader, so the result reflects every committed write.
+    pub fn with_requires_leader(mut self, requires_leader: bool) -> Self {
+        self.requires_leader = requires_leader;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct EventStreamId(pub Uuid);
 
 impl EventStreamId {
+    /// A random (v4) id. Use this when creation order doesn't matter.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// A time-ordered (v7) id. Ids created later sort later, which keeps index
+    /// locality good when many aggregates are created at a high rate.
+    #[cfg(feature = "v7")]
+    pub fn new_v7() -> Self {
+        Self(Uuid::now_v7())
+    }
+
     pub fn from_uuid(uuid: Uuid) -> Self {
         Self(uuid)
     }
+
+    /// The all-zeros id. Stores and `execute` reject it unless told otherwise.
+    pub fn nil() -> Self {
+        Self(Uuid::nil())
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.0.is_nil()
+    }
+
+    /// A name-based (v5) id, so the same natural key, such as an email address or
+    /// order number, always maps to the same stream. `namespace` keeps equal names
+    /// of different kinds of aggregate apart.
+    pub fn from_name(namespace: Uuid, name: &str) -> Self {
+        Self(Uuid::new_v5(&namespace, name.as_bytes()))
+    }
 }
 
 impl Default for EventStreamId {
@@ -41,10 +440,88 @@ impl std::fmt::Display for EventStreamId {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An `EventStreamId` bound to the type of the events its stream holds, for use
+/// with `EventStore::publish_typed` and `EventStore::read_typed`, so code that
+/// reads and writes the stream directly can't use another event type by mistake.
+pub struct TypedStream<E> {
+    id: EventStreamId,
+    event_type: PhantomData<fn() -> E>,
+}
+
+impl<E: Event> TypedStream<E> {
+    pub fn new(id: EventStreamId) -> Self {
+        Self {
+            id,
+            event_type: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> &EventStreamId {
+        &self.id
+    }
+
+    pub fn into_id(self) -> EventStreamId {
+        self.id
+    }
+}
+
+// Implemented by hand so that `E` needn't implement these traits itself.
+impl<E> Clone for TypedStream<E> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            event_type: PhantomData,
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for TypedStream<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TypedStream").field(&self.id).finish()
+    }
+}
+
+impl<E> PartialEq for TypedStream<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<E> Eq for TypedStream<E> {}
+
+impl<E> std::hash::Hash for TypedStream<E> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<E> From<TypedStream<E>> for EventStreamId {
+    fn from(stream: TypedStream<E>) -> Self {
+        stream.id
+    }
+}
+
+/// Fails with `Error::InvalidConfig` for the nil stream id, which almost always
+/// comes from an id that was never set. `execute` checks it and so does each
+/// store, so running a command on the nil id needs both opt-outs:
+/// `ExecuteConfig::with_nil_stream_id_allowed` and the store's own.
+pub(crate) fn reject_nil_stream_id(stream_id: &EventStreamId) -> Result<(), Error> {
+    if stream_id.is_nil() {
+        return Err(Error::InvalidConfig {
+            message: "stream id cannot be the nil UUID".to_string(),
+            parameter: Some("stream_id".to_string()),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct EventStreamVersion(u64);
 
 impl EventStreamVersion {
+    /// The version of a stream's first event.
+    pub const ZERO: Self = Self(0);
+
     pub fn new(version: u64) -> Self {
         Self(version)
     }
@@ -53,3 +530,93 @@ impl EventStreamVersion {
         self.0
     }
 }
+
+impl From<u64> for EventStreamVersion {
+    fn from(version: u64) -> Self {
+        Self(version)
+    }
+}
+
+impl From<EventStreamVersion> for u64 {
+    fn from(version: EventStreamVersion) -> Self {
+        version.0
+    }
+}
+
+/// Saturates at `u64::MAX` rather than overflowing.
+impl Add<u64> for EventStreamVersion {
+    type Output = Self;
+
+    fn add(self, rhs: u64) -> Self {
+        Self(self.0.saturating_add(rhs))
+    }
+}
+
+/// What an append expected of its stream's current version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedVersion {
+    Any,
+    NoStream,
+    StreamExists,
+    Exact(EventStreamVersion),
+}
+
+impl ExpectedVersion {
+    /// The exact version expected, if any.
+    pub fn version(&self) -> Option<EventStreamVersion> {
+        match self {
+            ExpectedVersion::Exact(version) => Some(*version),
+            _ => None,
+        }
+    }
+}
+
+/// `publish` takes `None` to mean that any version is acceptable.
+impl From<Option<EventStreamVersion>> for ExpectedVersion {
+    fn from(version: Option<EventStreamVersion>) -> Self {
+        version.map_or(ExpectedVersion::Any, ExpectedVersion::Exact)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogPosition {
+    commit: u64,
+    prepare: u64,
+}
+
+impl LogPosition {
+    pub fn new(commit: u64, prepare: u64) -> Self {
+        Self { commit, prepare }
+    }
+
+    pub fn commit(&self) -> u64 {
+        self.commit
+    }
+
+    pub fn prepare(&self) -> u64 {
+        self.prepare
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendResult {
+    next_expected_version: EventStreamVersion,
+    position: LogPosition,
+}
+
+impl AppendResult {
+    pub fn new(next_expected_version: EventStreamVersion, position: LogPosition) -> Self {
+        Self {
+            next_expected_version,
+            position,
+        }
+    }
+
+    pub fn next_expected_version(&self) -> EventStreamVersion {
+        self.next_expected_version
+    }
+
+    pub fn position(&self) -> LogPosition {
+        self.position
+    }
+}
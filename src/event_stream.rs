@@ -0,0 +1,272 @@
+use crate::command::AggregateState;
+use crate::dyn_event_store::RawEventStream;
+use crate::error::Error;
+use crate::event::{Event, original_timestamp};
+use crate::event_store::{EventStreamVersion, LogPosition};
+#[cfg(feature = "kurrent")]
+use crate::kurrent_adapter::{recorded_content_type, resolved_record};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use std::marker::PhantomData;
+
+pub struct EventStream<E: Event> {
+    source: EventSource,
+    stream_name: String,
+    known_event_types: Vec<String>,
+    after: Option<EventStreamVersion>,
+    last_version: Option<EventStreamVersion>,
+    last_position: Option<LogPosition>,
+    last_original_timestamp: Option<DateTime<Utc>>,
+    head_version: Option<EventStreamVersion>,
+    type_marker: PhantomData<E>,
+}
+
+struct Record {
+    event_type: String,
+    content_type: String,
+    data: Bytes,
+    version: EventStreamVersion,
+    position: Option<LogPosition>,
+    original_timestamp: Option<DateTime<Utc>>,
+}
+
+enum EventSource {
+    #[cfg(feature = "kurrent")]
+    Kurrent(eventstore::ReadStream),
+    Raw(RawEventStream),
+}
+
+impl<E: Event> EventStream<E> {
+    #[cfg(feature = "kurrent")]
+    pub(crate) fn new(stream: eventstore::ReadStream, stream_name: String) -> Self {
+        Self::with_source(EventSource::Kurrent(stream), stream_name)
+    }
+
+    pub(crate) fn from_raw(stream: RawEventStream, stream_name: String) -> Self {
+        Self::with_source(EventSource::Raw(stream), stream_name)
+    }
+
+    pub(crate) fn empty(stream_name: String) -> Self {
+        Self::from_raw(futures::stream::empty().boxed(), stream_name)
+    }
+
+    fn with_source(source: EventSource, stream_name: String) -> Self {
+        Self {
+            source,
+            stream_name,
+            known_event_types: E::all_event_types(),
+            after: None,
+            last_version: None,
+            last_position: None,
+            last_original_timestamp: None,
+            head_version: None,
+            type_marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn skip_through(mut self, version: EventStreamVersion) -> Self {
+        self.after = Some(version);
+        self
+    }
+
+    pub async fn next(&mut self) -> Result<Option<(E, EventStreamVersion)>, Error> {
+        loop {
+            let Some(record) = self.next_record().await? else {
+                return Ok(None);
+            };
+            if self
+                .after
+                .is_some_and(|after| record.version.value() <= after.value())
+            {
+                continue;
+            }
+            self.last_version = Some(record.version);
+            self.last_position = record.position;
+            self.last_original_timestamp = record.original_timestamp;
+            return self.decode(&record).map(Some);
+        }
+    }
+
+    pub(crate) fn with_head_version(mut self, head_version: Option<EventStreamVersion>) -> Self {
+        self.head_version = head_version;
+        self
+    }
+
+    /// The stream's last version when the read started, if the store probed it.
+    /// Events appended since then are not counted.
+    pub fn head_version(&self) -> Option<EventStreamVersion> {
+        self.head_version
+    }
+
+    /// An estimate of how many events `next` has left to return, from the probed
+    /// head version; `None` when the head wasn't probed. Hidden or skipped events
+    /// and concurrent appends make it approximate, so use it for progress
+    /// reporting only.
+    pub fn remaining(&self) -> Option<u64> {
+        let head = self.head_version?.value();
+        Some(match self.last_version.or(self.after) {
+            Some(read) => head.saturating_sub(read.value()),
+            None => head.saturating_add(1),
+        })
+    }
+
+    /// The global log position of the event last returned by `next`, when the
+    /// store records one.
+    pub fn last_position(&self) -> Option<LogPosition> {
+        self.last_position
+    }
+
+    /// When the event last returned by `next` originally happened, if it was
+    /// written by `Kurrent::import`. Its server-assigned creation time is when it
+    /// was imported.
+    pub fn last_original_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.last_original_timestamp
+    }
+
+    /// Folds the remaining events onto `initial` with `AggregateState::apply`,
+    /// calling `on_event` with each event and its version as it is applied, so one
+    /// read can both build state and feed other projections. Returns the final
+    /// state and the version of the last event, or `None` if there were none.
+    pub async fn replay_into<S: AggregateState<E>>(
+        &mut self,
+        initial: S,
+        mut on_event: impl FnMut(&E, EventStreamVersion),
+    ) -> Result<(S, Option<EventStreamVersion>), Error> {
+        let mut state = initial;
+        let mut last = None;
+        while let Some((event, version)) = self.next().await? {
+            on_event(&event, version);
+            state = state.apply(&event);
+            last = Some(version);
+        }
+        Ok((state, last))
+    }
+
+    /// Groups the remaining events into batches of up to `size` events (a `size`
+    /// of 0 is treated as 1). Events are read as each batch is requested, so the
+    /// stream is never buffered beyond one batch.
+    pub fn chunks(self, size: usize) -> EventChunks<E> {
+        EventChunks {
+            stream: self,
+            size: size.max(1),
+            pending_error: None,
+        }
+    }
+
+    async fn next_record(&mut self) -> Result<Option<Record>, Error> {
+        match &mut self.source {
+            #[cfg(feature = "kurrent")]
+            EventSource::Kurrent(stream) => loop {
+                match stream.next().await.or_else(|err| match err {
+                    eventstore::Error::ResourceNotFound => Ok(None),
+                    eventstore::Error::ResourceDeleted => Err(Error::StreamDeleted {
+                        stream: self.stream_name.clone(),
+                    }),
+                    eventstore::Error::DeadlineExceeded => Err(Error::DeadlineExceeded {
+                        stream: self.stream_name.clone(),
+                    }),
+                    other => Err(Error::EventStoreOther(other)),
+                })? {
+                    None => return Ok(None),
+                    Some(resolved) => {
+                        if let Some((event, version)) = resolved_record(&resolved) {
+                            return Ok(Some(Record {
+                                event_type: event.event_type.clone(),
+                                content_type: recorded_content_type(event),
+                                data: event.data.clone(),
+                                version,
+                                position: Some(event.position.into()),
+                                original_timestamp: original_timestamp(&event.custom_metadata),
+                            }));
+                        }
+                    }
+                }
+            },
+            EventSource::Raw(stream) => match stream.next().await {
+                None => Ok(None),
+                Some(record) => {
+                    let (raw, version) = record?;
+                    Ok(Some(Record {
+                        original_timestamp: raw.metadata.as_deref().and_then(original_timestamp),
+                        event_type: raw.event_type,
+                        content_type: raw.content_type,
+                        data: raw.data,
+                        version,
+                        position: None,
+                    }))
+                }
+            },
+        }
+    }
+
+    fn decode(&self, record: &Record) -> Result<(E, EventStreamVersion), Error> {
+        let event = decode_event(
+            &self.stream_name,
+            &self.known_event_types,
+            &record.event_type,
+            &record.content_type,
+            &record.data,
+            record.version,
+        )?;
+        Ok((event, version))
+    }
+}
+
+/// Batches of events from an `EventStream`, created by `EventStream::chunks`.
+pub struct EventChunks<E: Event> {
+    stream: EventStream<E>,
+    size: usize,
+    pending_error: Option<Error>,
+}
+
+impl<E: Event> EventChunks<E> {
+    /// The next batch, holding `size` events unless the stream ends first. An error
+    /// part-way through a batch is returned on the following call, after the
+    /// events read before it.
+    pub async fn next(&mut self) -> Result<Option<Vec<(E, EventStreamVersion)>>, Error> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+        let mut chunk = Vec::new();
+        while chunk.len() < self.size {
+            match self.stream.next().await {
+                Ok(Some(event)) => chunk.push(event),
+                Ok(None) => break,
+                Err(error) if chunk.is_empty() => return Err(error),
+                Err(error) => {
+                    self.pending_error = Some(error);
+                    break;
+                }
+            }
+        }
+        Ok((!chunk.is_empty()).then_some(chunk))
+    }
+}
+
+/// Deserializes one stored event, first decompressing it if its content type
+/// marks it as zstd-compressed (with the `zstd` feature).
+#[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+pub(crate) fn decode_event<E: Event>(
+    stream_name: &str,
+    known_event_types: &[String],
+    event_type: &str,
+    content_type: &str,
+    data: &[u8],
+    version: EventStreamVersion,
+) -> Result<E, Error> {
+    if !known_event_types.is_empty() && !known_event_types.iter().any(|known| known == event_type) {
+        return Err(Error::UnexpectedEventType {
+            stream: stream_name.to_string(),
+            event_type: event_type.to_string(),
+        });
+    }
+    #[cfg(feature = "zstd")]
+    let data = &*crate::compression::decompressed(content_type, data)?;
+    serde_json::from_slice::<E>(data).map_err(|source| Error::EventDeserializationFailed {
+        stream: stream_name.to_string(),
+        version,
+        event_type: event_type.to_string(),
+        source,
+    })
+}
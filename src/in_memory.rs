@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use uuid::Uuid;
+
+use crate::dyn_event_store::{DynEventStore, RawEvent, RawEventStream};
+use crate::error::version_mismatch;
+use crate::event_store::reject_nil_stream_id;
+use crate::{
+    Error, Event, EventStore, EventStream, EventStreamId, EventStreamVersion, ExpectedVersion,
+};
+
+/// An `EventStore` kept entirely in process memory, for tests and local tooling.
+/// Clones share the same streams.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryEventStore {
+    streams: Arc<Mutex<HashMap<EventStreamId, InMemoryStream>>>,
+    allow_nil_stream_id: bool,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryStream {
+    events: Vec<RawEvent>,
+    deleted: bool,
+}
+
+impl InMemoryEventStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets this store publish to and read the nil stream id; see
+    /// `ExecuteConfig::with_nil_stream_id_allowed`.
+    pub fn with_nil_stream_id_allowed(mut self, allowed: bool) -> Self {
+        self.allow_nil_stream_id = allowed;
+        self
+    }
+
+    /// Tombstones the stream: later reads and appends fail with `Error::StreamDeleted`.
+    pub fn delete(&self, stream_id: EventStreamId) {
+        let mut streams = self.streams.lock().unwrap();
+        let stream = streams.entry(stream_id).or_default();
+        stream.events.clear();
+        stream.deleted = true;
+    }
+
+    fn append(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: ExpectedVersion,
+    ) -> Result<(), Error> {
+        self.check_stream_id(&stream_id)?;
+        let mut streams = self.streams.lock().unwrap();
+        let stream = streams.entry(stream_id.clone()).or_default();
+        if stream.deleted {
+            return Err(Error::StreamDeleted {
+                stream: stream_id.to_string(),
+            });
+        }
+
+        let current = stream
+            .events
+            .len()
+            .checked_sub(1)
+            .map(|v| EventStreamVersion::new(v as u64));
+        let matches = match expected_version {
+            ExpectedVersion::Any => true,
+            ExpectedVersion::NoStream => current.is_none(),
+            ExpectedVersion::StreamExists => current.is_some(),
+            ExpectedVersion::Exact(expected) => current == Some(expected),
+        };
+        if !matches {
+            return Err(version_mismatch(stream_id, expected_version, current));
+        }
+
+        stream.events.extend(events);
+        Ok(())
+    }
+
+    fn check_stream_id(&self, stream_id: &EventStreamId) -> Result<(), Error> {
+        if self.allow_nil_stream_id {
+            return Ok(());
+        }
+        reject_nil_stream_id(stream_id)
+    }
+
+    fn head_version(&self, stream_id: &EventStreamId) -> Option<EventStreamVersion> {
+        let streams = self.streams.lock().unwrap();
+        streams
+            .get(stream_id)
+            .and_then(|stream| stream.events.len().checked_sub(1))
+            .map(|version| EventStreamVersion::new(version as u64))
+    }
+
+    fn read(&self, stream_id: EventStreamId) -> Result<RawEventStream, Error> {
+        self.check_stream_id(&stream_id)?;
+        let streams = self.streams.lock().unwrap();
+        let events = match streams.get(&stream_id) {
+            Some(stream) if stream.deleted => {
+                return Err(Error::StreamDeleted {
+                    stream: stream_id.to_string(),
+                });
+            }
+            Some(stream) => stream.events.clone(),
+            None => vec![],
+        };
+
+        let records = events
+            .into_iter()
+            .enumerate()
+            .map(|(version, event)| Ok((event, EventStreamVersion::new(version as u64))));
+        Ok(futures::stream::iter(records).boxed())
+    }
+}
+
+impl EventStore for InMemoryEventStore {
+    async fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| RawEvent::from_event_at(event, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.append(stream_id, events, expected_version.into())
+    }
+
+    async fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> Result<(), Error> {
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| RawEvent::from_event_at(event, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.append(stream_id, events, ExpectedVersion::NoStream)
+    }
+
+    async fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(index, (id, event))| {
+                RawEvent::from_event_at(event, index).map(|raw| RawEvent {
+                    id: Some(*id),
+                    ..raw
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.append(stream_id, events, expected_version.into())
+    }
+
+    async fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        _events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.append(stream_id, serialized, expected_version.into())
+    }
+
+    async fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        _events: Vec<E>,
+        serialized: Vec<RawEvent>,
+    ) -> Result<(), Error> {
+        self.append(stream_id, serialized, ExpectedVersion::NoStream)
+    }
+
+    async fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<EventStream<E>, Error> {
+        let stream_name = stream_id.to_string();
+        let head = self.head_version(&stream_id);
+        Ok(EventStream::from_raw(self.read(stream_id)?, stream_name).with_head_version(head))
+    }
+}
+
+impl DynEventStore for InMemoryEventStore {
+    fn publish_raw(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let result = self.append(stream_id, events, expected_version.into());
+        Box::pin(async move { result })
+    }
+
+    fn read_raw(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<RawEventStream, Error>> {
+        let result = self.read(stream_id);
+        Box::pin(async move { result })
+    }
+}
@@ -1,23 +1,237 @@
+mod export;
+mod filter;
+mod listing;
+mod naming;
+mod pool;
+mod server_info;
 mod settings;
-mod stream;
+mod snapshot;
+mod subscription;
+mod tenant;
 
-pub use settings::ConnectionSettings;
-pub use stream::EventStream;
+pub use export::ImportMode;
+pub use filter::EventFilter;
+pub use listing::ListStreamsOptions;
+pub use naming::{PrefixedStreamNaming, StreamNaming, UuidStreamNaming};
+pub use pool::KurrentPool;
+pub use server_info::{ServerFeature, ServerInfo};
+pub use settings::{ConnectionSettings, NodePreference};
+pub use snapshot::KurrentSnapshotStore;
+use subscription::DEFAULT_SUBSCRIPTION_BUFFER;
+pub use subscription::{AllEvent, AllSubscription, Subscription};
+pub use tenant::TenantKurrent;
 
+use crate::dyn_event_store::{DynEventStore, RawEvent, RawEventStream};
 use crate::error::Error;
-use crate::event::Event;
-use crate::event_store::{EventStore, EventStreamId, EventStreamVersion};
+use crate::event::{Event, ORIGINAL_TIMESTAMP_METADATA_KEY, serialize_event, validate_event};
+use crate::event_store::{
+    AppendResult, EventStore, EventStreamId, EventStreamVersion, ExpectedVersion, LogPosition,
+    ReadOptions, ReadOutcome, reject_nil_stream_id,
+};
+use crate::event_stream::EventStream;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use eventstore::AppendToStreamOptions;
+use export::ExportedEvent;
+use futures::future::BoxFuture;
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use uuid::Uuid;
+
+/// How many events `Kurrent::append_from_stream` sends per append when
+/// `with_max_append_events` isn't set.
+const DEFAULT_STREAMED_APPEND_EVENTS: usize = 500;
+
+/// How often `Kurrent::publish_and_wait` checks whether its write is readable.
+const OBSERVE_POLL_INTERVAL: Duration = Duration::from_millis(20);
 
 #[derive(Clone)]
 pub struct Kurrent {
     pub client: eventstore::Client,
+    /// The settings the client was built from, if it was built here.
+    settings: Option<ConnectionSettings>,
+    deadline: Option<Duration>,
+    max_append_events: Option<usize>,
+    subscription_buffer: usize,
+    naming: Arc<dyn StreamNaming>,
+    write_mode: WriteMode,
+    allow_nil_stream_id: bool,
+}
+
+/// What an append without an expected version (`publish` with `None`) expects of
+/// the stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Appends whatever the stream's version, which disables optimistic
+    /// concurrency: concurrent writers are never detected.
+    #[default]
+    Any,
+    /// Only appends to a stream that doesn't exist yet, so the write fails with
+    /// `Error::EventStoreVersionMismatch` if anyone else created it first.
+    NoStream,
 }
 
+/// The stream `Kurrent::connect` reads to check the server is reachable. It is
+/// never written, so the read finds nothing.
+const CONNECTION_CHECK_STREAM: &str = "mneme-connection-check";
+
 impl Kurrent {
+    /// Creates a store without contacting the server: the connection is made
+    /// lazily, so a wrong host or port only surfaces on the first read or append.
+    /// Use `connect` to check connectivity up front.
     pub fn new(settings: &ConnectionSettings) -> Result<Self, Error> {
         let client = eventstore::Client::new(settings.to_client_settings()?)?;
-        Ok(Self { client })
+        Ok(Self {
+            settings: Some(settings.clone()),
+            ..Self::from_client(client)
+        })
+    }
+
+    /// Wraps a client built elsewhere, for `eventstore::ClientSettings` options
+    /// `ConnectionSettings` doesn't cover or to share one client with direct
+    /// `eventstore` use. The store's own options start at their defaults.
+    pub fn from_client(client: eventstore::Client) -> Self {
+        Self {
+            client,
+            settings: None,
+            deadline: None,
+            max_append_events: None,
+            subscription_buffer: DEFAULT_SUBSCRIPTION_BUFFER,
+            naming: Arc::new(UuidStreamNaming),
+            write_mode: WriteMode::default(),
+            allow_nil_stream_id: false,
+        }
+    }
+
+    /// Creates a store like `new`, then makes one small read to check the server is
+    /// reachable and accepts the credentials. An unreachable server or a timed-out
+    /// check fails with the retryable `Error::ConnectionFailed`; anything else, such
+    /// as rejected credentials, is returned as `Error::EventStoreOther` and isn't
+    /// worth retrying. Useful for validating configuration at startup; the
+    /// connection can still drop later.
+    pub async fn connect(settings: &ConnectionSettings) -> Result<Self, Error> {
+        let store = Self::new(settings)?;
+        let options = store.read_options().max_count(1);
+        let checked = match store
+            .client
+            .read_stream(CONNECTION_CHECK_STREAM, &options)
+            .await
+        {
+            Ok(mut stream) => stream.next().await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        match checked {
+            Ok(()) | Err(eventstore::Error::ResourceNotFound) => Ok(store),
+            Err(
+                source @ (eventstore::Error::GrpcConnectionError(_)
+                | eventstore::Error::DeadlineExceeded),
+            ) => Err(Error::ConnectionFailed { source }),
+            Err(source) => Err(Error::EventStoreOther(source)),
+        }
+    }
+
+    /// The server's version and the gRPC methods and features it supports, for
+    /// enabling newer features only where the server has them. Queried on a
+    /// connection of its own, so it's only available on stores built with `new`
+    /// or `connect` (not `from_client`) that verify the server's certificate;
+    /// other stores fail with `Error::InvalidConfig`. Servers older than 21.10
+    /// don't report their features and fail with `Error::ServerInfoUnavailable`.
+    pub async fn server_info(&self) -> Result<ServerInfo, Error> {
+        let Some(settings) = &self.settings else {
+            return Err(Error::InvalidConfig {
+                message: "server_info needs a store built from ConnectionSettings".to_string(),
+                parameter: None,
+            });
+        };
+        server_info::fetch(settings).await
+    }
+
+    /// Applies `deadline` to every read and append made through this store, so
+    /// calls fail with `Error::DeadlineExceeded` instead of hanging (for example
+    /// during a leader election). Builders and writers can override it per call.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Reads, writes and subscribes to the nil stream id; see
+    /// `ExecuteConfig::with_nil_stream_id_allowed`.
+    pub fn with_nil_stream_id_allowed(mut self, allowed: bool) -> Self {
+        self.allow_nil_stream_id = allowed;
+        self
+    }
+
+    /// Stores every stream under the name `naming` gives it instead of its bare UUID.
+    pub fn with_stream_naming(mut self, naming: impl StreamNaming + 'static) -> Self {
+        self.naming = Arc::new(naming);
+        self
+    }
+
+    /// Chooses what appends without an expected version expect. `execute` relies on
+    /// this for commands on streams it found empty, so `WriteMode::NoStream` also
+    /// makes it detect a concurrent creation of the stream.
+    pub fn with_write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.write_mode = write_mode;
+        self
+    }
+
+    pub fn write_mode(&self) -> WriteMode {
+        self.write_mode
+    }
+
+    /// The name of the stream `stream_id` is stored in.
+    pub fn stream_name(&self, stream_id: &EventStreamId) -> String {
+        self.naming.stream_name(stream_id)
+    }
+
+    /// A view of this store that prefixes every stream name with `tenant_id`.
+    pub fn for_tenant(&self, tenant_id: impl Into<String>) -> TenantKurrent {
+        TenantKurrent::new(self, tenant_id.into())
+    }
+
+    /// A `SnapshotStore` that keeps snapshots in this store, next to the streams
+    /// they summarize.
+    pub fn snapshot_store(&self) -> KurrentSnapshotStore {
+        KurrentSnapshotStore::new(self)
+    }
+
+    /// Splits `publish` calls into appends of at most `max_events` events, keeping
+    /// each below the server's maximum append size. Every append after the first
+    /// expects the version the previous one ended at, so a large publish never
+    /// conflicts with itself, but a failure part-way leaves the earlier chunks
+    /// committed.
+    pub fn with_max_append_events(mut self, max_events: usize) -> Result<Self, Error> {
+        if max_events == 0 {
+            return Err(Error::InvalidConfig {
+                message: "max_append_events cannot be 0".to_string(),
+                parameter: Some("max_append_events".to_string()),
+            });
+        }
+        self.max_append_events = Some(max_events);
+        Ok(self)
+    }
+
+    /// How many events a `Subscription` reads ahead of its consumer before it
+    /// stops reading from the server. Defaults to 256.
+    pub fn with_subscription_buffer(mut self, capacity: usize) -> Result<Self, Error> {
+        if capacity == 0 {
+            return Err(Error::InvalidConfig {
+                message: "subscription_buffer cannot be 0".to_string(),
+                parameter: Some("subscription_buffer".to_string()),
+            });
+        }
+        self.subscription_buffer = capacity;
+        Ok(self)
+    }
+
+    pub fn subscription_buffer(&self) -> usize {
+        self.subscription_buffer
     }
 
     pub fn from_env() -> Result<Self, Error> {
@@ -29,56 +243,768 @@ impl Kurrent {
         EventStreamBuilder::new(self.clone(), stream_id)
     }
 
+    /// Reads the `$ce-{category}` stream maintained by the server's category projection.
+    pub fn category_stream_builder(&self, category: &str) -> EventStreamBuilder {
+        EventStreamBuilder::named(self.clone(), format!("$ce-{category}"))
+    }
+
+    /// Reads every event of `event_type` across all streams, from the `$et-{event_type}`
+    /// stream maintained by the server's `$by_event_type` projection.
+    pub async fn read_event_type<E: Event>(
+        &self,
+        event_type: &str,
+        options: ReadOptions,
+    ) -> Result<EventStream<E>, Error> {
+        let builder = EventStreamBuilder::named(self.clone(), format!("$et-{event_type}"));
+        let builder = match options.after() {
+            Some(after) => builder.position(position_after(after)),
+            None => builder,
+        };
+        builder
+            .with_options(|read_options| read_options.requires_leader(options.requires_leader()))
+            .read()
+            .await
+    }
+
+    /// Lists the ids of every stream in the store, read from the server's
+    /// `$streams` projection. Streams whose names weren't produced by this
+    /// store's `StreamNaming`, and system streams, are skipped.
+    pub fn list_streams(
+        &self,
+        options: ListStreamsOptions,
+    ) -> impl Stream<Item = Result<EventStreamId, Error>> + Send + 'static {
+        let store = self.clone();
+        let naming = self.naming.clone();
+        futures::stream::once(async move {
+            let read_options = store
+                .read_options()
+                .resolve_link_tos()
+                .requires_leader(options.requires_leader());
+            let events = match store.client.read_stream("$streams", &read_options).await {
+                Ok(stream) => stream,
+                Err(eventstore::Error::ResourceNotFound) => {
+                    return Ok(futures::stream::empty().boxed());
+                }
+                Err(source) => return Err(named_read_error("$streams".to_string(), source)),
+            };
+            let events = futures::stream::unfold(events, |mut stream| async move {
+                match stream.next().await {
+                    Ok(Some(event)) => Some((Ok(event), stream)),
+                    Ok(None) | Err(eventstore::Error::ResourceNotFound) => None,
+                    Err(e) => Some((Err(named_read_error("$streams".to_string(), e)), stream)),
+                }
+            });
+            Ok(listing::stream_ids(events, naming, options).boxed())
+        })
+        .try_flatten()
+    }
+
     pub fn stream_writer(&self, stream_id: EventStreamId) -> EventStreamWriter {
         EventStreamWriter::new(self.clone(), stream_id)
     }
 
+    /// Subscribes to `stream_id`, starting after `after` (`None` starts from the
+    /// beginning of the stream).
+    pub async fn subscribe<E: Event + 'static>(
+        &self,
+        stream_id: EventStreamId,
+        after: Option<EventStreamVersion>,
+    ) -> Result<Subscription<E>, Error> {
+        self.check_stream_id(&stream_id)?;
+        let start = match after {
+            Some(version) => eventstore::StreamPosition::Position(version.value()),
+            None => eventstore::StreamPosition::Start,
+        };
+        let options = eventstore::SubscribeToStreamOptions::default()
+            .start_from(start)
+            .resolve_link_tos();
+        let stream_name = stream_id.to_string();
+        let subscription = self
+            .client
+            .subscribe_to_stream(self.stream_name(&stream_id), &options)
+            .await;
+        Ok(Subscription::new(
+            subscription,
+            stream_name,
+            self.subscription_buffer,
+        ))
+    }
+
+    /// Subscribes to the events of every stream that pass `filter`, starting after
+    /// `after` (`None` starts from the beginning of `$all`). Filtering happens on
+    /// the server, which is much cheaper than skipping events client-side.
+    pub async fn subscribe_to_all(
+        &self,
+        filter: EventFilter,
+        after: Option<LogPosition>,
+    ) -> AllSubscription {
+        let start = match after {
+            Some(position) => eventstore::StreamPosition::Position(eventstore::Position {
+                commit: position.commit(),
+                prepare: position.prepare(),
+            }),
+            None => eventstore::StreamPosition::Start,
+        };
+        let options = eventstore::SubscribeToAllOptions::default().position(start);
+        let options = match filter.to_subscription_filter() {
+            Some(filter) => options.filter(filter),
+            None => options,
+        };
+        let subscription = self.client.subscribe_to_all(&options).await;
+        AllSubscription::new(subscription, self.subscription_buffer)
+    }
+
+    /// Hides every event before `version` from reads and lets scavenging reclaim
+    /// them, typically once a snapshot at `version` has been written.
+    pub async fn truncate_before(
+        &self,
+        stream_id: EventStreamId,
+        version: EventStreamVersion,
+    ) -> Result<(), Error> {
+        let head = self.stream_head(stream_id.clone()).await?;
+        if head.is_none_or(|head| version.value() > head.value()) {
+            return Err(Error::TruncateBeyondHead {
+                stream: stream_id,
+                requested: version,
+                head,
+            });
+        }
+
+        let mut metadata = match self
+            .client
+            .get_stream_metadata(self.stream_name(&stream_id), &Default::default())
+            .await
+            .map_err(|source| read_error(stream_id.clone(), source))?
+        {
+            eventstore::StreamMetadataResult::Success(versioned) => versioned.metadata,
+            _ => eventstore::StreamMetadata::default(),
+        };
+        metadata.truncate_before = Some(version.value());
+
+        self.client
+            .set_stream_metadata(self.stream_name(&stream_id), &Default::default(), &metadata)
+            .await
+            .map_err(|source| read_error(stream_id, source))?;
+        Ok(())
+    }
+
+    /// Like `read_stream`, but distinguishes a stream that was never written from
+    /// one whose events have all been truncated away.
+    pub async fn read_stream_outcome<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<ReadOutcome<E>, Error> {
+        if self.stream_head(stream_id.clone()).await?.is_some() {
+            return Ok(ReadOutcome::Stream(self.read_stream(stream_id).await?));
+        }
+
+        match self
+            .client
+            .get_stream_metadata(self.stream_name(&stream_id), &Default::default())
+            .await
+            .map_err(|source| read_error(stream_id.clone(), source))?
+        {
+            eventstore::StreamMetadataResult::Success(versioned) => Ok(ReadOutcome::Empty {
+                head_version: versioned
+                    .metadata
+                    .truncate_before
+                    .and_then(|truncate_before| truncate_before.checked_sub(1))
+                    .map(EventStreamVersion::new),
+            }),
+            eventstore::StreamMetadataResult::Deleted => Err(Error::StreamDeleted {
+                stream: stream_id.to_string(),
+            }),
+            eventstore::StreamMetadataResult::NotFound => Ok(ReadOutcome::NotFound),
+        }
+    }
+
+    /// The stream's current settings, read from its `$$` metadata stream, or
+    /// `None` if none have been set. This is the same `eventstore::StreamMetadata`
+    /// that `truncate_before` writes. Reading it needs the stream's metadata read
+    /// permission.
+    pub async fn read_stream_metadata_raw(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<Option<eventstore::StreamMetadata>, Error> {
+        match self
+            .client
+            .get_stream_metadata(self.stream_name(&stream_id), &Default::default())
+            .await
+            .map_err(|source| read_error(stream_id.clone(), source))?
+        {
+            eventstore::StreamMetadataResult::Success(versioned) => Ok(Some(versioned.metadata)),
+            eventstore::StreamMetadataResult::NotFound => Ok(None),
+            eventstore::StreamMetadataResult::Deleted => Err(Error::StreamDeleted {
+                stream: stream_id.to_string(),
+            }),
+        }
+    }
+
+    /// Appends already-serialized events verbatim, for forwarding or migrating
+    /// events without round-tripping them through an `Event` type.
+    pub async fn append_raw(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<AppendResult, Error> {
+        let events = events
+            .into_iter()
+            .map(raw_event_data)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.append_events(stream_id, events, self.expected_revision(expected_version))
+            .await
+            .map(AppendResult::from)
+    }
+
+    /// Reads the stream's events without decoding them, for tooling that handles
+    /// streams of mixed event types. Errors are mapped as for typed reads.
+    pub async fn read_raw_with(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<RawEventStream, Error> {
+        self.check_stream_id(&stream_id)?;
+        let read_options = self
+            .read_options()
+            .requires_leader(options.requires_leader());
+        let read_options = match options.after() {
+            Some(after) => read_options.position(position_after(after)),
+            None => read_options,
+        };
+        let stream = match self
+            .client
+            .read_stream(self.stream_name(&stream_id), &read_options)
+            .await
+        {
+            Ok(stream) => stream,
+            Err(eventstore::Error::ResourceNotFound) => {
+                return Ok(futures::stream::empty().boxed());
+            }
+            Err(source) => return Err(read_error(stream_id, source)),
+        };
+
+        let events = futures::stream::unfold(stream, move |mut stream| {
+            let stream_id = stream_id.clone();
+            async move {
+                loop {
+                    match stream.next().await {
+                        Ok(Some(resolved)) => {
+                            let Some((event, version)) = resolved_record(&resolved) else {
+                                continue;
+                            };
+                            return Some((Ok((raw_event(event), version)), stream));
+                        }
+                        Ok(None) | Err(eventstore::Error::ResourceNotFound) => return None,
+                        Err(e) => return Some((Err(read_error(stream_id, e)), stream)),
+                    }
+                }
+            }
+        });
+        Ok(events.boxed())
+    }
+
+    /// Writes every event of the stream to `writer` as newline-delimited JSON, one
+    /// line per event holding its version, type, content type, id, payload and
+    /// metadata, without decoding it into an `Event` type. Returns how many
+    /// events were written; `import_stream` reads the output back.
+    pub async fn export_stream(
+        &self,
+        stream_id: EventStreamId,
+        writer: impl AsyncWrite + Unpin,
+    ) -> Result<usize, Error> {
+        let mut writer = writer;
+        let mut events = self
+            .read_raw_with(stream_id, ReadOptions::default())
+            .await?;
+        let mut count = 0;
+        while let Some(record) = events.next().await {
+            let (event, version) = record?;
+            let mut line = serde_json::to_vec(&ExportedEvent::new(event, version))?;
+            line.push(b'\n');
+            writer.write_all(&line).await?;
+            count += 1;
+        }
+        writer.flush().await?;
+        Ok(count)
+    }
+
+    /// Appends the events `export_stream` wrote to `reader` to the stream, verbatim
+    /// and keeping their ids, with `mode` deciding what the stream has to hold
+    /// beforehand. Events are appended as they are read, in batches like
+    /// `append_from_stream`, so a failure part-way leaves the earlier batches
+    /// committed. Returns how many were appended.
+    pub async fn import_stream(
+        &self,
+        stream_id: EventStreamId,
+        reader: impl AsyncRead + Unpin,
+        mode: ImportMode,
+    ) -> Result<usize, Error> {
+        let batch_size = self
+            .max_append_events
+            .unwrap_or(DEFAULT_STREAMED_APPEND_EVENTS);
+        let mut lines = BufReader::new(reader).lines();
+        let mut expected_revision = None;
+        let mut batch = vec![];
+        let mut count = 0;
+        let mut done = false;
+        while !done {
+            match lines.next_line().await? {
+                Some(line) if line.trim().is_empty() => continue,
+                Some(line) => {
+                    let exported: ExportedEvent = serde_json::from_str(&line)?;
+                    expected_revision.get_or_insert(mode.expected_revision(exported.version()));
+                    batch.push(raw_event_data(exported.into_raw_event()?)?);
+                    if batch.len() < batch_size {
+                        continue;
+                    }
+                }
+                None => done = true,
+            }
+            if let Some(expected) = expected_revision.filter(|_| !batch.is_empty()) {
+                count += batch.len();
+                let result = self
+                    .append_events(stream_id.clone(), std::mem::take(&mut batch), expected)
+                    .await?;
+                expected_revision = Some(eventstore::ExpectedRevision::Exact(
+                    result.next_expected_version,
+                ));
+            }
+        }
+        Ok(count)
+    }
+
+    /// Reads the stream like `read_stream`, first probing its head so that
+    /// `EventStream::remaining` can estimate progress. The probe costs one extra
+    /// read of a single event.
+    pub async fn read_stream_with_head<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<EventStream<E>, Error> {
+        let head = self.stream_head(stream_id.clone()).await?;
+        Ok(self.read_stream(stream_id).await?.with_head_version(head))
+    }
+
+    async fn stream_head(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<Option<EventStreamVersion>, Error> {
+        self.stream_builder(stream_id).head_only().await
+    }
+
     pub async fn append_to_stream(
-        &mut self,
+        &self,
         stream_id: EventStreamId,
         options: &AppendToStreamOptions,
         events: Vec<eventstore::EventData>,
     ) -> Result<eventstore::WriteResult, Error> {
+        self.check_stream_id(&stream_id)?;
         self.client
-            .append_to_stream(stream_id.clone(), options, events)
-            .await
-            .map_err(|source| match source {
-                eventstore::Error::ResourceNotFound => Error::EventStoreStreamNotFound(stream_id),
-                eventstore::Error::WrongExpectedVersion { current, expected } => {
-                    Error::EventStoreVersionMismatch {
-                        stream: stream_id,
-                        expected: extract_revision(&expected),
-                        actual: extract_current_revision(&current),
-                        source,
-                    }
+            .append_to_stream(self.stream_name(&stream_id), options, events)
+            .await
+            .map_err(|source| append_error(stream_id, source))
+    }
+
+    /// Publishes like `EventStore::publish`, also returning where the events landed
+    /// in the global log, for ordering them against other streams.
+    pub async fn append<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<AppendResult, Error> {
+        let events: Vec<eventstore::EventData> = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| event_data(event, index))
+            .collect::<Result<_, _>>()?;
+
+        self.append_events(stream_id, events, self.expected_revision(expected_version))
+            .await
+            .map(AppendResult::from)
+    }
+
+    /// Publishes like `append`, then polls the stream's head on the node reads
+    /// prefer (see `ConnectionSettingsBuilder::default_node_preference`) until the
+    /// write is readable, for read-your-writes consistency when reads may go to a
+    /// follower. Returns the version of the
+    /// last event written, or `Error::WriteNotObserved` if reads haven't caught up
+    /// within `timeout`; the events are appended either way.
+    pub async fn publish_and_wait<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+        timeout: Duration,
+    ) -> Result<EventStreamVersion, Error> {
+        let version = self
+            .append(stream_id.clone(), events, expected_version)
+            .await?
+            .next_expected_version();
+        let deadline = Instant::now() + timeout;
+        loop {
+            let head = self
+                .stream_builder(stream_id.clone())
+                .requires_leader(false)
+                .head_only()
+                .await?;
+            if head.is_some_and(|head| head.value() >= version.value()) {
+                return Ok(version);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::WriteNotObserved {
+                    stream: self.stream_name(&stream_id),
+                    version,
+                    timeout,
+                });
+            }
+            tokio::time::sleep(OBSERVE_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// Appends `events` under the given ids so that the append can be retried
+    /// safely when it is unknown whether an earlier attempt committed, such as
+    /// after its future was dropped mid-flight. Returns the version of the last
+    /// event.
+    ///
+    /// The append expects exactly `expected_version` (`None` for a new stream).
+    /// If the stream has moved on, the events after `expected_version` are read
+    /// back from the leader: when they start with these ids in this order, an
+    /// earlier attempt committed them and their version is returned; otherwise the
+    /// conflict is returned as `Error::EventStoreVersionMismatch`. The events are
+    /// therefore appended exactly once, provided every attempt passes the same
+    /// ids, events and expected version, and the batch is appended in one write
+    /// (no more than `with_max_append_events`).
+    pub async fn publish_idempotent<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<EventStreamVersion, Error> {
+        let ids: Vec<Uuid> = events.iter().map(|(id, _)| *id).collect();
+        let events: Vec<eventstore::EventData> = events
+            .iter()
+            .enumerate()
+            .map(|(index, (id, event))| event_data(event, index).map(|data| data.id(*id)))
+            .collect::<Result<_, _>>()?;
+        let expected_revision = match expected_version {
+            Some(version) => eventstore::ExpectedRevision::Exact(version.value()),
+            None => eventstore::ExpectedRevision::NoStream,
+        };
+
+        match self
+            .append_events(stream_id.clone(), events, expected_revision)
+            .await
+        {
+            Ok(result) => Ok(EventStreamVersion::new(result.next_expected_version)),
+            Err(conflict @ Error::EventStoreVersionMismatch { .. }) => {
+                match self
+                    .committed_version(stream_id, expected_version, &ids)
+                    .await?
+                {
+                    Some(version) => Ok(version),
+                    None => Err(conflict),
                 }
-                e => Error::EventStoreOther(e),
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The version of the last of `ids`, if the events right after
+    /// `expected_version` carry exactly those ids, in order.
+    async fn committed_version(
+        &self,
+        stream_id: EventStreamId,
+        expected_version: Option<EventStreamVersion>,
+        ids: &[Uuid],
+    ) -> Result<Option<EventStreamVersion>, Error> {
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let options = ReadOptions::new().with_requires_leader(true);
+        let options = match expected_version {
+            Some(version) => options.starting_after(version),
+            None => options,
+        };
+        let mut events = self.read_raw_with(stream_id, options).await?;
+        let mut last = None;
+        for id in ids {
+            match events.next().await.transpose()? {
+                Some((event, version)) if event.id == Some(*id) => last = Some(version),
+                _ => return Ok(None),
+            }
+        }
+        Ok(last)
+    }
+
+    /// Appends events imported from another system, recording each event's
+    /// `Event::occurred_at` as `original_timestamp` in its metadata so reads can
+    /// recover it with `EventStream::last_original_timestamp`. The server still
+    /// stamps every event with the time of this append. Events without an
+    /// `occurred_at` are written as by `append`.
+    pub async fn import<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<AppendResult, Error> {
+        let events = events
+            .into_iter()
+            .map(|event| {
+                let occurred_at = event.occurred_at();
+                (event, occurred_at)
+            })
+            .collect();
+        self.import_events(stream_id, events, expected_version)
+            .await
+    }
+
+    /// Imports like `import`, taking each event's original timestamp explicitly
+    /// instead of from `Event::occurred_at`.
+    pub async fn import_at<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(E, DateTime<Utc>)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<AppendResult, Error> {
+        let events = events
+            .into_iter()
+            .map(|(event, occurred_at)| (event, Some(occurred_at)))
+            .collect();
+        self.import_events(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn import_events<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(E, Option<DateTime<Utc>>)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<AppendResult, Error> {
+        let events: Vec<eventstore::EventData> = events
+            .iter()
+            .enumerate()
+            .map(|(index, (event, occurred_at))| match occurred_at {
+                Some(occurred_at) => imported_event_data(event, index, *occurred_at),
+                None => event_data(event, index),
             })
+            .collect::<Result<_, _>>()?;
+
+        self.append_events(stream_id, events, self.expected_revision(expected_version))
+            .await
+            .map(AppendResult::from)
+    }
+
+    /// Appends events as `iter` produces them, without collecting them first. See
+    /// `append_from_stream`.
+    pub async fn append_iter<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: impl IntoIterator<Item = E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<Option<AppendResult>, Error> {
+        self.append_from_stream(stream_id, futures::stream::iter(events), expected_version)
+            .await
+    }
+
+    /// Appends events as `events` produces them, serializing and sending them in
+    /// batches of `with_max_append_events` (500 if unset) so memory stays flat
+    /// however many there are. Each batch after the first expects the version the
+    /// previous one ended at; a failure part-way leaves the earlier batches
+    /// committed. Returns where the last batch landed, or `None` if there were no
+    /// events.
+    pub async fn append_from_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: impl Stream<Item = E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<Option<AppendResult>, Error> {
+        let batch_size = self
+            .max_append_events
+            .unwrap_or(DEFAULT_STREAMED_APPEND_EVENTS);
+        let mut batches = std::pin::pin!(events.chunks(batch_size));
+        let mut expected_revision = self.expected_revision(expected_version);
+        let mut index = 0;
+        let mut last = None;
+        while let Some(batch) = batches.next().await {
+            let batch = batch
+                .iter()
+                .map(|event| {
+                    index += 1;
+                    event_data(event, index - 1)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let result = self
+                .append_events(stream_id.clone(), batch, expected_revision)
+                .await?;
+            expected_revision = eventstore::ExpectedRevision::Exact(result.next_expected_version);
+            last = Some(AppendResult::from(result));
+        }
+        Ok(last)
+    }
+
+    /// Creates the stream with `events`, or leaves it alone if it already exists,
+    /// for idempotent seeding. Returns whether this call created it; any error
+    /// other than the stream already existing is returned.
+    pub async fn append_if_not_exists<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> Result<bool, Error> {
+        let events: Vec<eventstore::EventData> = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| event_data(event, index))
+            .collect::<Result<_, _>>()?;
+
+        match self
+            .append_events(stream_id, events, eventstore::ExpectedRevision::NoStream)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(Error::EventStoreVersionMismatch {
+                expected: ExpectedVersion::NoStream,
+                ..
+            }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn append_events(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<eventstore::EventData>,
+        expected_revision: eventstore::ExpectedRevision,
+    ) -> Result<eventstore::WriteResult, Error> {
+        self.check_stream_id(&stream_id)?;
+        let chunk_size = self.max_append_events.unwrap_or(usize::MAX);
+        let mut expected_revision = expected_revision;
+        let mut remaining = events;
+        loop {
+            let rest = remaining.split_off(chunk_size.min(remaining.len()));
+            let options = self.append_options().expected_revision(expected_revision);
+            let result = self
+                .client
+                .append_to_stream(self.stream_name(&stream_id), &options, remaining)
+                .await
+                .map_err(|source| append_error(stream_id.clone(), source))?;
+            if rest.is_empty() {
+                return Ok(result);
+            }
+            expected_revision = eventstore::ExpectedRevision::Exact(result.next_expected_version);
+            remaining = rest;
+        }
+    }
+
+    fn check_stream_id(&self, stream_id: &EventStreamId) -> Result<(), Error> {
+        if self.allow_nil_stream_id {
+            return Ok(());
+        }
+        reject_nil_stream_id(stream_id)
+    }
+
+    fn read_options(&self) -> eventstore::ReadStreamOptions {
+        let options = eventstore::ReadStreamOptions::default();
+        match self.deadline {
+            Some(deadline) => options.deadline(deadline),
+            None => options,
+        }
+    }
+
+    /// Appends require the leader whatever node the client prefers for reads.
+    fn append_options(&self) -> AppendToStreamOptions {
+        let options = AppendToStreamOptions::default().requires_leader(true);
+        match self.deadline {
+            Some(deadline) => options.deadline(deadline),
+            None => options,
+        }
+    }
+
+    fn expected_revision(
+        &self,
+        expected_version: Option<EventStreamVersion>,
+    ) -> eventstore::ExpectedRevision {
+        match expected_version {
+            Some(v) => eventstore::ExpectedRevision::Exact(v.value()),
+            None => match self.write_mode {
+                WriteMode::Any => eventstore::ExpectedRevision::Any,
+                WriteMode::NoStream => eventstore::ExpectedRevision::NoStream,
+            },
+        }
     }
 }
 
 impl EventStore for Kurrent {
     async fn publish<E: Event>(
-        &mut self,
+        &self,
         stream_id: EventStreamId,
         events: Vec<E>,
         expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.append(stream_id, events, expected_version).await?;
+        Ok(())
+    }
+
+    async fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
     ) -> Result<(), Error> {
         let events: Vec<eventstore::EventData> = events
             .iter()
-            .map(|event| {
-                let event_type = event.event_type();
-                eventstore::EventData::json(&event_type, &event)
-                    .map_err(Error::EventDeserializationError)
-            })
+            .enumerate()
+            .map(|(index, event)| event_data(event, index))
             .collect::<Result<_, _>>()?;
 
-        let options = AppendToStreamOptions::default().expected_revision(match expected_version {
-            Some(v) => eventstore::ExpectedRevision::Exact(v.value()),
-            None => eventstore::ExpectedRevision::Any,
-        });
+        self.append_events(stream_id, events, eventstore::ExpectedRevision::NoStream)
+            .await?;
+        Ok(())
+    }
 
-        self.append_to_stream(stream_id, &options, events).await?;
+    async fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        let events: Vec<eventstore::EventData> = events
+            .iter()
+            .enumerate()
+            .map(|(index, (id, event))| event_data(event, index).map(|data| data.id(*id)))
+            .collect::<Result<_, _>>()?;
+
+        self.append_events(stream_id, events, self.expected_revision(expected_version))
+            .await?;
+        Ok(())
+    }
+
+    async fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        _events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.append_raw(stream_id, serialized, expected_version)
+            .await?;
+        Ok(())
+    }
+
+    async fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        _events: Vec<E>,
+        serialized: Vec<RawEvent>,
+    ) -> Result<(), Error> {
+        let events = serialized
+            .into_iter()
+            .map(raw_event_data)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.append_events(stream_id, events, eventstore::ExpectedRevision::NoStream)
+            .await?;
         Ok(())
     }
 
@@ -86,39 +1012,117 @@ impl EventStore for Kurrent {
         &self,
         stream_id: EventStreamId,
     ) -> Result<EventStream<E>, Error> {
-        let stream = self
+        self.check_stream_id(&stream_id)?;
+        let stream_name = stream_id.to_string();
+        match self
             .client
-            .read_stream(stream_id.clone(), &Default::default())
+            .read_stream(self.stream_name(&stream_id), &self.read_options())
+            .await
+        {
+            Ok(stream) => Ok(EventStream::new(stream, stream_name)),
+            Err(eventstore::Error::ResourceNotFound) => Ok(EventStream::empty(stream_name)),
+            Err(source) => Err(read_error(stream_id, source)),
+        }
+    }
+
+    async fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> Result<EventStream<E>, Error> {
+        self.stream_builder(stream_id)
+            .position(position_after(after))
+            .read()
+            .await
+    }
+
+    async fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<EventStream<E>, Error> {
+        let builder = match options.after() {
+            Some(after) => self
+                .stream_builder(stream_id)
+                .position(position_after(after)),
+            None => self.stream_builder(stream_id),
+        };
+        builder
+            .with_options(|read_options| read_options.requires_leader(options.requires_leader()))
+            .read()
+            .await
+    }
+
+    async fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<Option<EventStreamVersion>, Error> {
+        self.stream_builder(stream_id)
+            .with_options(|read_options| read_options.requires_leader(options.requires_leader()))
+            .head_only()
             .await
-            .map(|stream| EventStream {
-                stream,
-                type_marker: std::marker::PhantomData,
-            })
-            .map_err(|source| match source {
-                eventstore::Error::ResourceNotFound => Error::EventStoreStreamNotFound(stream_id),
-                e => Error::EventStoreOther(e),
-            })?;
-        Ok(stream)
+    }
+}
+
+impl DynEventStore for Kurrent {
+    fn publish_raw(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.append_raw(stream_id, events, expected_version).await?;
+            Ok(())
+        })
+    }
+
+    fn read_raw(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<RawEventStream, Error>> {
+        Box::pin(self.read_raw_with(stream_id, ReadOptions::new()))
     }
 }
 
 pub struct EventStreamBuilder {
     store: Kurrent,
-    stream_id: EventStreamId,
+    stream_id: Option<EventStreamId>,
+    stream_name: String,
     read_options: eventstore::ReadStreamOptions,
+    resolve_links: bool,
 }
 
 impl EventStreamBuilder {
     pub fn new(store: Kurrent, stream_id: EventStreamId) -> Self {
         Self {
+            read_options: store.read_options(),
+            stream_name: store.stream_name(&stream_id),
             store,
-            stream_id,
-            read_options: Default::default(),
+            stream_id: Some(stream_id),
+            resolve_links: true,
         }
     }
 
+    pub(crate) fn named(store: Kurrent, stream_name: String) -> Self {
+        Self {
+            read_options: store.read_options(),
+            store,
+            stream_id: None,
+            stream_name,
+            resolve_links: true,
+        }
+    }
+
+    /// Whether link events (as found in category and projection streams) are
+    /// replaced by the events they point to. Defaults to `true`.
+    pub fn resolve_links(mut self, resolve: bool) -> Self {
+        self.resolve_links = resolve;
+        self
+    }
+
     pub fn max_count(mut self, count: u64) -> Self {
-        self.read_options = self.read_options.max_count(count.try_into().unwrap());
+        // Counts beyond what the platform can address can't be returned anyway.
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        self.read_options = self.read_options.max_count(count);
         self
     }
 
@@ -127,23 +1131,78 @@ impl EventStreamBuilder {
         self
     }
 
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.read_options = self.read_options.deadline(deadline);
+        self
+    }
+
+    /// Reads from the leader even if the connection prefers another node.
+    pub fn requires_leader(mut self, requires_leader: bool) -> Self {
+        self.read_options = self.read_options.requires_leader(requires_leader);
+        self
+    }
+
+    /// Adjusts the underlying `eventstore` read options directly, for settings
+    /// this builder doesn't wrap.
+    pub fn with_options(
+        mut self,
+        f: impl FnOnce(eventstore::ReadStreamOptions) -> eventstore::ReadStreamOptions,
+    ) -> Self {
+        self.read_options = f(self.read_options);
+        self
+    }
+
     pub async fn read<E: Event>(self) -> Result<EventStream<E>, Error> {
-        let stream = self
+        if let Some(stream_id) = &self.stream_id {
+            self.store.check_stream_id(stream_id)?;
+        }
+        let read_options = if self.resolve_links {
+            self.read_options.resolve_link_tos()
+        } else {
+            self.read_options
+        };
+        match self
             .store
             .client
-            .read_stream(self.stream_id.clone(), &self.read_options)
+            .read_stream(self.stream_name.clone(), &read_options)
             .await
-            .map(|stream| EventStream {
-                stream,
-                type_marker: std::marker::PhantomData,
-            })
-            .map_err(|source| match source {
-                eventstore::Error::ResourceNotFound => {
-                    Error::EventStoreStreamNotFound(self.stream_id)
-                }
-                e => Error::EventStoreOther(e),
-            })?;
-        Ok(stream)
+        {
+            Ok(stream) => Ok(EventStream::new(stream, self.stream_name)),
+            Err(eventstore::Error::ResourceNotFound) => Ok(EventStream::empty(self.stream_name)),
+            Err(source) => Err(stream_read_error(self.stream_id, self.stream_name, source)),
+        }
+    }
+
+    /// The version of the stream's last event, or `None` if it has none, without
+    /// decoding any event. Replaces the position, count and direction set so far
+    /// with a backwards read of the single last record, whose payload is ignored.
+    pub async fn head_only(self) -> Result<Option<EventStreamVersion>, Error> {
+        if let Some(stream_id) = &self.stream_id {
+            self.store.check_stream_id(stream_id)?;
+        }
+        let read_options = self
+            .read_options
+            .position(eventstore::StreamPosition::End)
+            .backwards()
+            .max_count(1);
+        let mut stream = match self
+            .store
+            .client
+            .read_stream(self.stream_name.clone(), &read_options)
+            .await
+        {
+            Ok(stream) => stream,
+            Err(eventstore::Error::ResourceNotFound) => return Ok(None),
+            Err(source) => return Err(stream_read_error(self.stream_id, self.stream_name, source)),
+        };
+
+        match stream.next().await {
+            Ok(Some(resolved)) => Ok(Some(EventStreamVersion::new(
+                resolved.get_original_event().revision,
+            ))),
+            Ok(None) | Err(eventstore::Error::ResourceNotFound) => Ok(None),
+            Err(source) => Err(stream_read_error(self.stream_id, self.stream_name, source)),
+        }
     }
 }
 
@@ -156,9 +1215,9 @@ pub struct EventStreamWriter {
 impl EventStreamWriter {
     pub fn new(store: Kurrent, stream_id: EventStreamId) -> Self {
         Self {
+            write_options: store.append_options(),
             store,
             stream_id,
-            write_options: Default::default(),
         }
     }
 
@@ -183,41 +1242,240 @@ impl EventStreamWriter {
         self
     }
 
-    pub async fn append<E: Event>(self, events: Vec<E>) -> Result<eventstore::WriteResult, Error> {
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.write_options = self.write_options.deadline(deadline);
+        self
+    }
+
+    /// Adjusts the underlying `eventstore` append options directly, for settings
+    /// this writer doesn't wrap.
+    pub fn with_options(
+        mut self,
+        f: impl FnOnce(AppendToStreamOptions) -> AppendToStreamOptions,
+    ) -> Self {
+        self.write_options = f(self.write_options);
+        self
+    }
+
+    pub async fn append<E: Event>(self, events: Vec<E>) -> Result<AppendResult, Error> {
+        self.store.check_stream_id(&self.stream_id)?;
         let events: Vec<eventstore::EventData> = events
             .iter()
-            .map(|event| {
-                let event_type = event.event_type();
-                eventstore::EventData::json(&event_type, &event)
-                    .map_err(Error::EventDeserializationError)
-            })
+            .enumerate()
+            .map(|(index, event)| event_data(event, index))
             .collect::<Result<_, _>>()?;
 
         self.store
             .client
-            .append_to_stream(self.stream_id.clone(), &self.write_options, events)
+            .append_to_stream(
+                self.store.stream_name(&self.stream_id),
+                &self.write_options,
+                events,
+            )
             .await
-            .map_err(|source| match source {
-                eventstore::Error::ResourceNotFound => {
-                    Error::EventStoreStreamNotFound(self.stream_id)
-                }
-                eventstore::Error::WrongExpectedVersion { current, expected } => {
-                    Error::EventStoreVersionMismatch {
-                        stream: self.stream_id,
-                        expected: extract_revision(&expected),
-                        actual: extract_current_revision(&current),
-                        source,
-                    }
-                }
-                e => Error::EventStoreOther(e),
-            })
+            .map(AppendResult::from)
+            .map_err(|source| append_error(self.stream_id, source))
+    }
+}
+
+impl eventstore::StreamName for EventStreamId {
+    fn into_stream_name(self) -> Bytes {
+        Bytes::from(self.0.to_string())
     }
 }
 
-fn extract_revision(expected: &eventstore::ExpectedRevision) -> Option<EventStreamVersion> {
+/// The event to decode and the version it sits at in the stream being read. For a
+/// resolved link that is the target event at the link's revision; links whose
+/// target has been deleted resolve to nothing and are skipped.
+pub(crate) fn resolved_record(
+    resolved: &eventstore::ResolvedEvent,
+) -> Option<(&eventstore::RecordedEvent, EventStreamVersion)> {
+    let event = resolved.event.as_ref()?;
+    let position = resolved.link.as_ref().unwrap_or(event);
+    Some((event, EventStreamVersion::new(position.revision)))
+}
+
+impl From<eventstore::WriteResult> for AppendResult {
+    fn from(result: eventstore::WriteResult) -> Self {
+        AppendResult::new(
+            EventStreamVersion::new(result.next_expected_version),
+            LogPosition::from(result.position),
+        )
+    }
+}
+
+/// The recorded event as stored, without decoding its payload.
+pub(crate) fn raw_event(event: &eventstore::RecordedEvent) -> RawEvent {
+    RawEvent {
+        event_type: event.event_type.clone(),
+        content_type: recorded_content_type(event),
+        data: event.data.clone(),
+        metadata: (!event.custom_metadata.is_empty()).then(|| event.custom_metadata.clone()),
+        id: Some(event.id),
+    }
+}
+
+impl From<eventstore::Position> for LogPosition {
+    fn from(position: eventstore::Position) -> Self {
+        LogPosition::new(position.commit, position.prepare)
+    }
+}
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+const CONTENT_TYPE_METADATA_KEY: &str = "content-type";
+
+fn event_data<E: Event>(event: &E, index: usize) -> Result<eventstore::EventData, Error> {
+    if event.content_type() == JSON_CONTENT_TYPE {
+        validate_event(event)?;
+        return eventstore::EventData::json(event.event_type(), event).map_err(|source| {
+            Error::EventSerializationFailed {
+                event_type: event.event_type(),
+                index,
+                source,
+            }
+        });
+    }
+    encoded_event_data(
+        &event.event_type(),
+        event.content_type(),
+        serialize_event(event, index)?.into(),
+    )
+}
+
+/// Replaces the metadata `event_data` writes, so a non-JSON content type is
+/// recorded again alongside the original timestamp.
+fn imported_event_data<E: Event>(
+    event: &E,
+    index: usize,
+    occurred_at: DateTime<Utc>,
+) -> Result<eventstore::EventData, Error> {
+    let mut metadata = serde_json::Map::new();
+    metadata.insert(
+        ORIGINAL_TIMESTAMP_METADATA_KEY.to_string(),
+        occurred_at.to_rfc3339().into(),
+    );
+    if event.content_type() != JSON_CONTENT_TYPE {
+        metadata.insert(
+            CONTENT_TYPE_METADATA_KEY.to_string(),
+            event.content_type().into(),
+        );
+    }
+    Ok(event_data(event, index)?.metadata_as_json(metadata)?)
+}
+
+/// Supplied metadata is written verbatim for JSON events. For any other content
+/// type it must be a JSON object, which the content type is recorded in.
+fn raw_event_data(event: RawEvent) -> Result<eventstore::EventData, Error> {
+    let event_data = match event.metadata {
+        None => encoded_event_data(&event.event_type, &event.content_type, event.data)?,
+        Some(metadata) if event.content_type == JSON_CONTENT_TYPE => {
+            encoded_event_data(&event.event_type, &event.content_type, event.data)?
+                .metadata(metadata)
+        }
+        Some(metadata) => {
+            let Ok(serde_json::Value::Object(mut metadata)) = serde_json::from_slice(&metadata)
+            else {
+                return Err(Error::EventValidationFailed {
+                    event_type: event.event_type,
+                    message: "metadata of a non-JSON event must be a JSON object".to_string(),
+                });
+            };
+            metadata.insert(
+                CONTENT_TYPE_METADATA_KEY.to_string(),
+                event.content_type.into(),
+            );
+            eventstore::EventData::binary(event.event_type, event.data)
+                .metadata_as_json(metadata)?
+        }
+    };
+    Ok(match event.id {
+        Some(id) => event_data.id(id),
+        None => event_data,
+    })
+}
+
+/// The server only distinguishes JSON from binary payloads, so any other content
+/// type is written as binary and recorded in the custom metadata.
+fn encoded_event_data(
+    event_type: &str,
+    content_type: &str,
+    data: Bytes,
+) -> Result<eventstore::EventData, Error> {
+    if content_type == JSON_CONTENT_TYPE {
+        // A `RawValue` serializes as the bytes it was parsed from, so the payload
+        // is only checked to be JSON, not rewritten.
+        let data: Box<serde_json::value::RawValue> = serde_json::from_slice(&data)?;
+        return Ok(eventstore::EventData::json(event_type, data)?);
+    }
+    Ok(eventstore::EventData::binary(event_type, data)
+        .metadata_as_json(serde_json::json!({ CONTENT_TYPE_METADATA_KEY: content_type }))?)
+}
+
+pub(crate) fn recorded_content_type(event: &eventstore::RecordedEvent) -> String {
+    serde_json::from_slice::<serde_json::Value>(&event.custom_metadata)
+        .ok()
+        .and_then(|metadata| {
+            metadata
+                .get(CONTENT_TYPE_METADATA_KEY)
+                .and_then(|content_type| content_type.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| JSON_CONTENT_TYPE.to_string())
+}
+
+fn position_after(version: EventStreamVersion) -> eventstore::StreamPosition<u64> {
+    eventstore::StreamPosition::Position(version.value().saturating_add(1))
+}
+
+fn append_error(stream_id: EventStreamId, source: eventstore::Error) -> Error {
+    match source {
+        eventstore::Error::WrongExpectedVersion { current, expected } => {
+            Error::EventStoreVersionMismatch {
+                stream: stream_id,
+                expected: extract_revision(&expected),
+                actual: extract_current_revision(&current),
+                source,
+            }
+        }
+        source => read_error(stream_id, source),
+    }
+}
+
+fn read_error(stream_id: EventStreamId, source: eventstore::Error) -> Error {
+    match source {
+        eventstore::Error::ResourceNotFound => Error::EventStoreStreamNotFound(stream_id),
+        source => named_read_error(stream_id.to_string(), source),
+    }
+}
+
+/// Maps a read error for a stream known by id where there is one, else by name.
+fn stream_read_error(
+    stream_id: Option<EventStreamId>,
+    stream_name: String,
+    source: eventstore::Error,
+) -> Error {
+    match stream_id {
+        Some(stream_id) => read_error(stream_id, source),
+        None => named_read_error(stream_name, source),
+    }
+}
+
+fn named_read_error(stream: String, source: eventstore::Error) -> Error {
+    match source {
+        eventstore::Error::ResourceDeleted => Error::StreamDeleted { stream },
+        eventstore::Error::DeadlineExceeded => Error::DeadlineExceeded { stream },
+        e => Error::EventStoreOther(e),
+    }
+}
+
+fn extract_revision(expected: &eventstore::ExpectedRevision) -> ExpectedVersion {
     match expected {
-        eventstore::ExpectedRevision::Exact(v) => Some(EventStreamVersion::new(*v)),
-        _ => None,
+        eventstore::ExpectedRevision::Any => ExpectedVersion::Any,
+        eventstore::ExpectedRevision::NoStream => ExpectedVersion::NoStream,
+        eventstore::ExpectedRevision::StreamExists => ExpectedVersion::StreamExists,
+        eventstore::ExpectedRevision::Exact(v) => {
+            ExpectedVersion::Exact(EventStreamVersion::new(*v))
+        }
     }
 }
 
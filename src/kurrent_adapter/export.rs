@@ -0,0 +1,237 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::dyn_event_store::RawEvent;
+use crate::event_store::EventStreamVersion;
+
+use super::JSON_CONTENT_TYPE;
+
+/// How `Kurrent::import_stream` appends the events it reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Appends after whatever the stream already holds.
+    #[default]
+    Append,
+    /// Only restores into a stream that doesn't exist yet.
+    NewStream,
+    /// Appends so the first imported event lands at the version it was exported
+    /// from, failing with `Error::EventStoreVersionMismatch` otherwise. Restoring a
+    /// whole stream into a new one this way keeps every event's version.
+    PreserveVersions,
+}
+
+impl ImportMode {
+    /// The expected revision for an import whose first event was exported from
+    /// `first`.
+    pub(super) fn expected_revision(
+        &self,
+        first: EventStreamVersion,
+    ) -> eventstore::ExpectedRevision {
+        match self {
+            ImportMode::Append => eventstore::ExpectedRevision::Any,
+            ImportMode::NewStream => eventstore::ExpectedRevision::NoStream,
+            ImportMode::PreserveVersions => match first.value() {
+                0 => eventstore::ExpectedRevision::NoStream,
+                version => eventstore::ExpectedRevision::Exact(version - 1),
+            },
+        }
+    }
+}
+
+/// One line of an export. JSON payloads are embedded byte for byte so the file
+/// stays readable and imports restore them exactly; anything else, including JSON
+/// that spans lines or has surrounding whitespace, is hex-encoded.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ExportedEvent {
+    version: u64,
+    event_type: String,
+    content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Box<RawValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_hex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<Box<RawValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata_hex: Option<String>,
+}
+
+impl ExportedEvent {
+    pub(super) fn new(event: RawEvent, version: EventStreamVersion) -> Self {
+        let (data, data_hex) = if event.content_type == JSON_CONTENT_TYPE {
+            split_payload(&event.data)
+        } else {
+            (None, Some(encode_hex(&event.data)))
+        };
+        let (metadata, metadata_hex) = match &event.metadata {
+            Some(metadata) => split_payload(metadata),
+            None => (None, None),
+        };
+        Self {
+            version: version.value(),
+            event_type: event.event_type,
+            content_type: event.content_type,
+            id: event.id,
+            data,
+            data_hex,
+            metadata,
+            metadata_hex,
+        }
+    }
+
+    pub(super) fn version(&self) -> EventStreamVersion {
+        EventStreamVersion::new(self.version)
+    }
+
+    pub(super) fn into_raw_event(self) -> Result<RawEvent, serde_json::Error> {
+        let data = join_payload(self.data, self.data_hex)?.unwrap_or_default();
+        let metadata = join_payload(self.metadata, self.metadata_hex)?;
+        Ok(RawEvent {
+            event_type: self.event_type,
+            content_type: self.content_type,
+            data,
+            metadata,
+            id: self.id,
+        })
+    }
+}
+
+fn split_payload(payload: &[u8]) -> (Option<Box<RawValue>>, Option<String>) {
+    match serde_json::from_slice::<Box<RawValue>>(payload) {
+        Ok(value) if value.get().as_bytes() == payload && !value.get().contains('\n') => {
+            (Some(value), None)
+        }
+        _ => (None, Some(encode_hex(payload))),
+    }
+}
+
+fn join_payload(
+    value: Option<Box<RawValue>>,
+    hex: Option<String>,
+) -> Result<Option<Bytes>, serde_json::Error> {
+    match (value, hex) {
+        (Some(value), _) => Ok(Some(Bytes::copy_from_slice(value.get().as_bytes()))),
+        (None, Some(hex)) => decode_hex(&hex).map(|bytes| Some(bytes.into())),
+        (None, None) => Ok(None),
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, serde_json::Error> {
+    let invalid = || <serde_json::Error as serde::de::Error>::custom("invalid hex payload");
+    if hex.len() % 2 != 0 {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_event(content_type: &str, data: &[u8], metadata: Option<&[u8]>) -> RawEvent {
+        RawEvent {
+            event_type: "Happened".to_string(),
+            content_type: content_type.to_string(),
+            data: Bytes::copy_from_slice(data),
+            metadata: metadata.map(Bytes::copy_from_slice),
+            id: Some(Uuid::new_v4()),
+        }
+    }
+
+    fn round_trip(event: RawEvent) -> RawEvent {
+        let line =
+            serde_json::to_string(&ExportedEvent::new(event, EventStreamVersion::new(3))).unwrap();
+        let exported: ExportedEvent = serde_json::from_str(&line).unwrap();
+        assert_eq!(exported.version(), EventStreamVersion::new(3));
+        exported.into_raw_event().unwrap()
+    }
+
+    #[test]
+    fn json_events_are_embedded_as_json() {
+        let event = raw_event(
+            JSON_CONTENT_TYPE,
+            br#"{"value":1}"#,
+            Some(br#"{"source":"test"}"#),
+        );
+        let line = serde_json::to_string(&ExportedEvent::new(
+            event.clone(),
+            EventStreamVersion::new(0),
+        ))
+        .unwrap();
+        let line: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(line["data"], serde_json::json!({ "value": 1 }));
+        assert_eq!(line["metadata"], serde_json::json!({ "source": "test" }));
+        assert_eq!(round_trip(event.clone()), event);
+    }
+
+    #[test]
+    fn json_payloads_keep_their_exact_bytes() {
+        let event = raw_event(
+            JSON_CONTENT_TYPE,
+            br#"{"zeta":1.50,"alpha":1e3}"#,
+            Some(br#"{"b":true,"a":false}"#),
+        );
+        let line = serde_json::to_string(&ExportedEvent::new(
+            event.clone(),
+            EventStreamVersion::new(0),
+        ))
+        .unwrap();
+        assert!(line.contains(r#""data":{"zeta":1.50,"alpha":1e3}"#));
+        assert_eq!(round_trip(event.clone()), event);
+
+        let spread = raw_event(JSON_CONTENT_TYPE, b"{\n  \"value\": 1\n}\n", None);
+        let line = serde_json::to_string(&ExportedEvent::new(
+            spread.clone(),
+            EventStreamVersion::new(0),
+        ))
+        .unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(round_trip(spread.clone()), spread);
+    }
+
+    #[test]
+    fn binary_payloads_survive_the_round_trip() {
+        let event = raw_event("application/octet-stream", &[0, 159, 255], Some(&[1, 2]));
+        assert_eq!(round_trip(event.clone()), event);
+    }
+
+    #[test]
+    fn invalid_hex_is_rejected() {
+        let exported: ExportedEvent = serde_json::from_str(
+            r#"{"version":0,"event_type":"Happened","content_type":"x","data_hex":"zz"}"#,
+        )
+        .unwrap();
+        assert!(exported.into_raw_event().is_err());
+    }
+
+    #[test]
+    fn preserving_versions_appends_after_the_previous_one() {
+        assert_eq!(
+            ImportMode::PreserveVersions.expected_revision(EventStreamVersion::new(0)),
+            eventstore::ExpectedRevision::NoStream
+        );
+        assert_eq!(
+            ImportMode::PreserveVersions.expected_revision(EventStreamVersion::new(5)),
+            eventstore::ExpectedRevision::Exact(4)
+        );
+        assert_eq!(
+            ImportMode::Append.expected_revision(EventStreamVersion::new(5)),
+            eventstore::ExpectedRevision::Any
+        );
+    }
+}
@@ -0,0 +1,72 @@
+/// Selects the events a `$all` subscription delivers. The server applies the
+/// filter, matching either event types or stream names against a set of
+/// prefixes or a regex.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    target: Option<FilterTarget>,
+    prefixes: Vec<String>,
+    regex: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterTarget {
+    EventType,
+    StreamName,
+}
+
+impl EventFilter {
+    /// Lets every event through, including system events.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Matches event types against the prefixes and regex added next.
+    pub fn on_event_type() -> Self {
+        Self {
+            target: Some(FilterTarget::EventType),
+            ..Self::default()
+        }
+    }
+
+    /// Matches stream names against the prefixes and regex added next.
+    pub fn on_stream_name() -> Self {
+        Self {
+            target: Some(FilterTarget::StreamName),
+            ..Self::default()
+        }
+    }
+
+    /// Skips system events (those whose type starts with `$`), such as the links
+    /// and stream metadata the server writes to `$all`.
+    pub fn exclude_system_events() -> Self {
+        Self::on_event_type().with_regex(r"^[^\$].*")
+    }
+
+    /// Adds a prefix; an event passes if it matches any of them.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Sets the regex an event must match. Use a negative lookahead such as
+    /// `^(?!audit-)` to exclude a prefix.
+    pub fn with_regex(mut self, regex: impl Into<String>) -> Self {
+        self.regex = Some(regex.into());
+        self
+    }
+
+    pub(crate) fn to_subscription_filter(&self) -> Option<eventstore::SubscriptionFilter> {
+        let filter = match self.target? {
+            FilterTarget::EventType => eventstore::SubscriptionFilter::on_event_type(),
+            FilterTarget::StreamName => eventstore::SubscriptionFilter::on_stream_name(),
+        };
+        let filter = self
+            .prefixes
+            .iter()
+            .fold(filter, |filter, prefix| filter.add_prefix(prefix));
+        Some(match &self.regex {
+            Some(regex) => filter.regex(regex),
+            None => filter,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use futures::{Stream, StreamExt};
+
+use super::StreamNaming;
+use crate::error::Error;
+use crate::event_store::EventStreamId;
+
+/// Options for `Kurrent::list_streams`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListStreamsOptions {
+    category: Option<String>,
+    requires_leader: bool,
+}
+
+impl ListStreamsOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    pub fn requires_leader(&self) -> bool {
+        self.requires_leader
+    }
+
+    /// Only list streams in `category`, i.e. those named `{category}-...`.
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Only read from the cluster leader, so the result reflects every committed write.
+    pub fn with_requires_leader(mut self, requires_leader: bool) -> Self {
+        self.requires_leader = requires_leader;
+        self
+    }
+}
+
+/// Turns the events of `$streams` into the ids of the streams they announce,
+/// each listed once, skipping system streams and names `naming` didn't produce.
+pub(super) fn stream_ids(
+    events: impl Stream<Item = Result<eventstore::ResolvedEvent, Error>> + Send + 'static,
+    naming: std::sync::Arc<dyn StreamNaming>,
+    options: ListStreamsOptions,
+) -> impl Stream<Item = Result<EventStreamId, Error>> + Send + 'static {
+    let mut seen = HashSet::new();
+    events.filter_map(move |event| {
+        let id = match event {
+            Ok(event) => base_stream_name(&event)
+                .filter(|name| !name.starts_with('$'))
+                .filter(|name| match &options.category {
+                    Some(category) => name
+                        .strip_prefix(category.as_str())
+                        .is_some_and(|rest| rest.starts_with('-')),
+                    None => true,
+                })
+                .and_then(|name| naming.stream_id(&name))
+                .filter(|id| seen.insert(id.clone()))
+                .map(Ok),
+            Err(e) => Some(Err(e)),
+        };
+        futures::future::ready(id)
+    })
+}
+
+/// The stream a `$streams` entry announces. Entries are links to the first event
+/// of each stream, or to its `$$` metadata stream when metadata was written first;
+/// links that didn't resolve carry the target as `{revision}@{stream}` data.
+fn base_stream_name(resolved: &eventstore::ResolvedEvent) -> Option<String> {
+    let event = resolved.event.as_ref()?;
+    let name = if event.event_type == "$>" {
+        let data = std::str::from_utf8(&event.data).ok()?;
+        data.split_once('@')?.1.to_string()
+    } else {
+        event.stream_id.clone()
+    };
+    Some(name.strip_prefix("$$").map(str::to_string).unwrap_or(name))
+}
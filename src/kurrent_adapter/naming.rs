@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+
+use uuid::Uuid;
+
+use crate::event_store::EventStreamId;
+
+/// Maps an `EventStreamId` to the name of the stream it is stored in. A `Kurrent`
+/// uses one strategy for every read and write, so they always agree.
+pub trait StreamNaming: Debug + Send + Sync {
+    fn stream_name(&self, stream_id: &EventStreamId) -> String;
+
+    /// The inverse of `stream_name`, or `None` for names this strategy didn't
+    /// produce. By default the name must end in the UUID.
+    fn stream_id(&self, stream_name: &str) -> Option<EventStreamId> {
+        let start = stream_name.len().checked_sub(36)?;
+        let uuid = Uuid::parse_str(stream_name.get(start..)?).ok()?;
+        Some(EventStreamId(uuid))
+    }
+}
+
+/// Names each stream after its bare UUID. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidStreamNaming;
+
+impl StreamNaming for UuidStreamNaming {
+    fn stream_name(&self, stream_id: &EventStreamId) -> String {
+        stream_id.to_string()
+    }
+
+    fn stream_id(&self, stream_name: &str) -> Option<EventStreamId> {
+        Uuid::parse_str(stream_name).ok().map(EventStreamId)
+    }
+}
+
+/// Names each stream `{prefix}{separator}{uuid}`, e.g. `Order-<uuid>` with the
+/// default `-` separator, which also puts the streams in the `$ce-Order` category.
+#[derive(Debug, Clone)]
+pub struct PrefixedStreamNaming {
+    prefix: String,
+    separator: String,
+}
+
+impl PrefixedStreamNaming {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            separator: "-".to_string(),
+        }
+    }
+
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+}
+
+impl StreamNaming for PrefixedStreamNaming {
+    fn stream_name(&self, stream_id: &EventStreamId) -> String {
+        format!("{}{}{}", self.prefix, self.separator, stream_id)
+    }
+
+    fn stream_id(&self, stream_name: &str) -> Option<EventStreamId> {
+        let uuid = stream_name
+            .strip_prefix(self.prefix.as_str())?
+            .strip_prefix(self.separator.as_str())?;
+        UuidStreamNaming.stream_id(uuid)
+    }
+}
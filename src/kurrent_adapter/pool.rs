@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use uuid::Uuid;
+
+use super::{ConnectionSettings, EventStream, Kurrent};
+use crate::dyn_event_store::RawEvent;
+use crate::error::Error;
+use crate::event::Event;
+use crate::event_store::{EventStore, EventStreamId, EventStreamVersion, ReadOptions};
+
+/// Holds `pool_size` independent clients built from the same settings and hands
+/// them out round-robin, one per operation.
+#[derive(Clone)]
+pub struct KurrentPool {
+    stores: Arc<[Kurrent]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl KurrentPool {
+    pub fn new(settings: &ConnectionSettings) -> Result<Self, Error> {
+        let stores = (0..settings.pool_size())
+            .map(|_| Kurrent::new(settings))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            stores: stores.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn from_env() -> Result<Self, Error> {
+        let settings = ConnectionSettings::from_env()?;
+        Self::new(&settings)
+    }
+
+    pub fn size(&self) -> usize {
+        self.stores.len()
+    }
+
+    /// The next store in the rotation.
+    pub fn get(&self) -> Kurrent {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.stores.len();
+        self.stores[index].clone()
+    }
+}
+
+impl EventStore for KurrentPool {
+    async fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.get()
+            .publish(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> Result<(), Error> {
+        self.get().publish_new(stream_id, events).await
+    }
+
+    async fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.get()
+            .publish_with_ids(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.get()
+            .publish_serialized(stream_id, events, serialized, expected_version)
+            .await
+    }
+
+    async fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+    ) -> Result<(), Error> {
+        self.get()
+            .publish_new_serialized(stream_id, events, serialized)
+            .await
+    }
+
+    async fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<EventStream<E>, Error> {
+        self.get().read_stream(stream_id).await
+    }
+
+    async fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> Result<EventStream<E>, Error> {
+        self.get().read_stream_from(stream_id, after).await
+    }
+
+    async fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<EventStream<E>, Error> {
+        self.get().read_stream_with(stream_id, options).await
+    }
+
+    async fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<Option<EventStreamVersion>, Error> {
+        self.get().head_version::<E>(stream_id, options).await
+    }
+}
@@ -0,0 +1,120 @@
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+
+use super::ConnectionSettings;
+use crate::error::Error;
+
+const GET_SUPPORTED_METHODS: &str =
+    "/event_store.client.server_features.ServerFeatures/GetSupportedMethods";
+
+/// The server's version and the gRPC methods it supports, as reported by its
+/// server-features endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub version: String,
+    pub features: Vec<ServerFeature>,
+}
+
+impl ServerInfo {
+    /// Whether the server supports `method` of `service`, both matched without
+    /// regard to case. `service` is the full gRPC name, such as
+    /// `event_store.client.persistent_subscriptions.PersistentSubscriptions`.
+    pub fn supports(&self, service: &str, method: &str) -> bool {
+        self.features.iter().any(|feature| {
+            feature.service.eq_ignore_ascii_case(service)
+                && feature.method.eq_ignore_ascii_case(method)
+        })
+    }
+}
+
+/// One gRPC method the server supports, with the optional features it offers
+/// for it (e.g. `"all"` for persistent subscriptions to `$all`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerFeature {
+    pub service: String,
+    pub method: String,
+    pub features: Vec<String>,
+}
+
+/// `event_store.client.Empty`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct Empty {}
+
+/// `event_store.client.server_features.SupportedMethods`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct SupportedMethods {
+    #[prost(message, repeated, tag = "1")]
+    methods: Vec<SupportedMethod>,
+    #[prost(string, tag = "2")]
+    event_store_server_version: String,
+}
+
+/// `event_store.client.server_features.SupportedMethod`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct SupportedMethod {
+    #[prost(string, tag = "1")]
+    method_name: String,
+    #[prost(string, tag = "2")]
+    service_name: String,
+    #[prost(string, repeated, tag = "3")]
+    features: Vec<String>,
+}
+
+/// Calls `ServerFeatures/GetSupportedMethods` on its own short-lived channel,
+/// since the `eventstore` client doesn't expose the one it uses.
+pub(super) async fn fetch(settings: &ConnectionSettings) -> Result<ServerInfo, Error> {
+    if settings.accepts_invalid_certs() {
+        return Err(Error::InvalidConfig {
+            message: "server_info can't skip certificate verification".to_string(),
+            parameter: Some("accept_invalid_certs".to_string()),
+        });
+    }
+    let scheme = if settings.tls() { "https" } else { "http" };
+    let uri = format!("{scheme}://{}:{}", settings.host(), settings.port());
+    let mut endpoint = Endpoint::from_shared(uri).map_err(unavailable)?;
+    if settings.tls() {
+        endpoint = endpoint
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(unavailable)?;
+    }
+    let channel = endpoint.connect().await.map_err(unavailable)?;
+
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.map_err(unavailable)?;
+    let response = grpc
+        .unary(
+            tonic::Request::new(Empty {}),
+            PathAndQuery::from_static(GET_SUPPORTED_METHODS),
+            ProstCodec::<Empty, SupportedMethods>::default(),
+        )
+        .await
+        .map_err(|status| match status.code() {
+            tonic::Code::Unimplemented => Error::ServerInfoUnavailable {
+                reason: "the server doesn't report its features; it predates 21.10".to_string(),
+                source: Some(Box::new(status)),
+            },
+            _ => unavailable(status),
+        })?
+        .into_inner();
+
+    Ok(ServerInfo {
+        version: response.event_store_server_version,
+        features: response
+            .methods
+            .into_iter()
+            .map(|method| ServerFeature {
+                service: method.service_name,
+                method: method.method_name,
+                features: method.features,
+            })
+            .collect(),
+    })
+}
+
+fn unavailable(source: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::ServerInfoUnavailable {
+        reason: source.to_string(),
+        source: Some(Box::new(source)),
+    }
+}
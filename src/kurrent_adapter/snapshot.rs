@@ -0,0 +1,135 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::{Kurrent, named_read_error};
+use crate::error::Error;
+use crate::event_store::{EventStreamId, EventStreamVersion};
+use crate::snapshot::{Snapshot, SnapshotStore};
+
+const SNAPSHOT_EVENT_TYPE: &str = "Snapshot";
+
+/// A `SnapshotStore` that keeps each stream's snapshot in KurrentDB itself, as
+/// the latest event of a `<stream>-snapshot` stream capped at one event. Built
+/// with `Kurrent::snapshot_store`, sharing that store's connection and naming.
+#[derive(Clone)]
+pub struct KurrentSnapshotStore {
+    store: Kurrent,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSnapshot {
+    version: u64,
+    state: serde_json::Value,
+}
+
+impl KurrentSnapshotStore {
+    pub(super) fn new(store: &Kurrent) -> Self {
+        Self {
+            store: store.clone(),
+        }
+    }
+
+    /// The name of the stream the snapshot of `stream_id` is stored in.
+    pub fn stream_name(&self, stream_id: &EventStreamId) -> String {
+        format!("{}-snapshot", self.store.stream_name(stream_id))
+    }
+
+    async fn load_snapshot(&self, stream_id: EventStreamId) -> Result<Option<Snapshot>, Error> {
+        let stream_name = self.stream_name(&stream_id);
+        let options = self
+            .store
+            .read_options()
+            .position(eventstore::StreamPosition::End)
+            .backwards()
+            .max_count(1);
+        let mut stream = match self
+            .store
+            .client
+            .read_stream(stream_name.clone(), &options)
+            .await
+        {
+            Ok(stream) => stream,
+            Err(eventstore::Error::ResourceNotFound) => return Ok(None),
+            Err(source) => return Err(named_read_error(stream_name, source)),
+        };
+
+        let event = match stream.next().await {
+            Ok(Some(resolved)) => resolved.get_original_event().clone(),
+            Ok(None) | Err(eventstore::Error::ResourceNotFound) => return Ok(None),
+            Err(source) => return Err(named_read_error(stream_name, source)),
+        };
+        let stored = event.as_json::<StoredSnapshot>().map_err(|source| {
+            Error::EventDeserializationFailed {
+                stream: stream_name,
+                version: EventStreamVersion::new(event.revision),
+                event_type: event.event_type.clone(),
+                source,
+            }
+        })?;
+        Ok(Some(Snapshot::new(
+            EventStreamVersion::new(stored.version),
+            stored.state,
+        )))
+    }
+
+    async fn save_snapshot(
+        &self,
+        stream_id: EventStreamId,
+        snapshot: Snapshot,
+    ) -> Result<(), Error> {
+        let stream_name = self.stream_name(&stream_id);
+        let stored = StoredSnapshot {
+            version: snapshot.version().value(),
+            state: snapshot.state().clone(),
+        };
+        let event =
+            eventstore::EventData::json(SNAPSHOT_EVENT_TYPE, &stored).map_err(|source| {
+                Error::EventSerializationFailed {
+                    event_type: SNAPSHOT_EVENT_TYPE.to_string(),
+                    index: 0,
+                    source,
+                }
+            })?;
+
+        let result = self
+            .store
+            .client
+            .append_to_stream(stream_name.clone(), &self.store.append_options(), event)
+            .await
+            .map_err(|source| named_read_error(stream_name.clone(), source))?;
+
+        // The first snapshot creates the stream; cap it so the server scavenges
+        // every snapshot but the latest.
+        if result.next_expected_version == 0 {
+            let mut metadata = eventstore::StreamMetadata::default();
+            metadata.max_count = Some(1);
+            self.store
+                .client
+                .set_stream_metadata(stream_name.clone(), &Default::default(), &metadata)
+                .await
+                .map_err(|source| named_read_error(stream_name, source))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for KurrentSnapshotStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KurrentSnapshotStore")
+            .finish_non_exhaustive()
+    }
+}
+
+impl SnapshotStore for KurrentSnapshotStore {
+    fn load(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<Option<Snapshot>, Error>> {
+        Box::pin(self.load_snapshot(stream_id))
+    }
+
+    fn save(
+        &self,
+        stream_id: EventStreamId,
+        snapshot: Snapshot,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(self.save_snapshot(stream_id, snapshot))
+    }
+}
@@ -0,0 +1,149 @@
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::{raw_event, recorded_content_type, resolved_record};
+use crate::dyn_event_store::RawEvent;
+use crate::error::Error;
+use crate::event::Event;
+use crate::event_store::{EventStreamVersion, LogPosition};
+use crate::event_stream::decode_event;
+
+pub(crate) const DEFAULT_SUBSCRIPTION_BUFFER: usize = 256;
+
+/// A catch-up subscription to a single stream: it delivers the events already
+/// recorded and then waits for new ones.
+///
+/// Events are read from the server by a background task into a channel holding
+/// at most the store's `subscription_buffer` events. When a slow consumer lets
+/// the channel fill, the task stops reading until `next` makes room, and the
+/// server in turn stops sending once the connection's flow-control window is
+/// full, so memory use stays bounded however far behind the consumer falls.
+/// Dropping the subscription stops the task.
+pub struct Subscription<E: Event> {
+    receiver: mpsc::Receiver<Result<(E, EventStreamVersion), Error>>,
+    stream_name: String,
+    task: JoinHandle<()>,
+}
+
+impl<E: Event + 'static> Subscription<E> {
+    pub(crate) fn new(
+        mut inner: eventstore::Subscription,
+        stream_name: String,
+        capacity: usize,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let task_stream_name = stream_name.clone();
+        let task = tokio::spawn(async move {
+            let known_event_types = E::all_event_types();
+            loop {
+                let item = match inner.next().await {
+                    Ok(resolved) => match resolved_record(&resolved) {
+                        Some((event, version)) => decode_event(
+                            &task_stream_name,
+                            &known_event_types,
+                            &event.event_type,
+                            &recorded_content_type(event),
+                            &event.data,
+                            version,
+                        )
+                        .map(|event| (event, version)),
+                        None => continue,
+                    },
+                    Err(eventstore::Error::ResourceDeleted) => Err(Error::StreamDeleted {
+                        stream: task_stream_name.clone(),
+                    }),
+                    Err(other) => Err(Error::EventStoreOther(other)),
+                };
+                let deleted = matches!(item, Err(Error::StreamDeleted { .. }));
+                // Waits while the buffer is full, which pauses reading from the server.
+                if sender.send(item).await.is_err() || deleted {
+                    break;
+                }
+            }
+        });
+        Self {
+            receiver,
+            stream_name,
+            task,
+        }
+    }
+
+    /// Waits for the next event. The returned version is the checkpoint to resume
+    /// from with `Kurrent::subscribe`.
+    pub async fn next(&mut self) -> Result<(E, EventStreamVersion), Error> {
+        match self.receiver.recv().await {
+            Some(item) => item,
+            // The task only stops on its own once the stream is deleted.
+            None => Err(Error::StreamDeleted {
+                stream: self.stream_name.clone(),
+            }),
+        }
+    }
+}
+
+impl<E: Event> Drop for Subscription<E> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// An event delivered by an `AllSubscription`, with where it was recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllEvent {
+    pub stream_name: String,
+    pub version: EventStreamVersion,
+    /// The checkpoint to resume from with `Kurrent::subscribe_to_all`.
+    pub position: LogPosition,
+    pub event: RawEvent,
+}
+
+/// A catch-up subscription to `$all`, delivering the events of every stream that
+/// pass its filter undecoded, since they span many event types. Buffering works
+/// as for `Subscription`.
+pub struct AllSubscription {
+    receiver: mpsc::Receiver<Result<AllEvent, Error>>,
+    task: JoinHandle<()>,
+}
+
+impl AllSubscription {
+    pub(crate) fn new(mut inner: eventstore::Subscription, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let task = tokio::spawn(async move {
+            loop {
+                let item = match inner.next().await {
+                    Ok(resolved) => match resolved.event.as_ref() {
+                        Some(event) => Ok(AllEvent {
+                            stream_name: event.stream_id.clone(),
+                            version: EventStreamVersion::new(event.revision),
+                            position: event.position.into(),
+                            event: raw_event(event),
+                        }),
+                        None => continue,
+                    },
+                    Err(other) => Err(Error::EventStoreOther(other)),
+                };
+                if sender.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Self { receiver, task }
+    }
+
+    /// Waits for the next event.
+    pub async fn next(&mut self) -> Result<AllEvent, Error> {
+        match self.receiver.recv().await {
+            Some(item) => item,
+            // `$all` can't be deleted, so the task only stops if it panicked.
+            None => Err(Error::StreamDeleted {
+                stream: "$all".to_string(),
+            }),
+        }
+    }
+}
+
+impl Drop for AllSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
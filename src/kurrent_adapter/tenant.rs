@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use super::{EventStream, Kurrent, StreamNaming, Subscription};
+use crate::dyn_event_store::RawEvent;
+use crate::error::Error;
+use crate::event::Event;
+use crate::event_store::{EventStore, EventStreamId, EventStreamVersion, ReadOptions};
+
+/// A `Kurrent` whose every stream name is prefixed with a tenant id, so one
+/// tenant can never read or write another tenant's streams. Built with
+/// `Kurrent::for_tenant`.
+#[derive(Clone)]
+pub struct TenantKurrent {
+    store: Kurrent,
+    tenant_id: String,
+}
+
+impl TenantKurrent {
+    pub(super) fn new(store: &Kurrent, tenant_id: String) -> Self {
+        let naming = TenantStreamNaming {
+            tenant_id: tenant_id.clone(),
+            inner: store.naming.clone(),
+        };
+        let mut store = store.clone();
+        store.naming = Arc::new(naming);
+        Self { store, tenant_id }
+    }
+
+    pub fn tenant_id(&self) -> &str {
+        &self.tenant_id
+    }
+
+    /// The name of the stream `stream_id` is stored in for this tenant.
+    pub fn stream_name(&self, stream_id: &EventStreamId) -> String {
+        self.store.stream_name(stream_id)
+    }
+
+    pub async fn subscribe<E: Event + 'static>(
+        &self,
+        stream_id: EventStreamId,
+        after: Option<EventStreamVersion>,
+    ) -> Result<Subscription<E>, Error> {
+        self.store.subscribe(stream_id, after).await
+    }
+}
+
+impl std::fmt::Debug for TenantKurrent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TenantKurrent")
+            .field("tenant_id", &self.tenant_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventStore for TenantKurrent {
+    async fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.store
+            .publish(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn publish_new<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+    ) -> Result<(), Error> {
+        self.store.publish_new(stream_id, events).await
+    }
+
+    async fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.store
+            .publish_with_ids(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.store
+            .publish_serialized(stream_id, events, serialized, expected_version)
+            .await
+    }
+
+    async fn publish_new_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+    ) -> Result<(), Error> {
+        self.store
+            .publish_new_serialized(stream_id, events, serialized)
+            .await
+    }
+
+    async fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<EventStream<E>, Error> {
+        self.store.read_stream(stream_id).await
+    }
+
+    async fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> Result<EventStream<E>, Error> {
+        self.store.read_stream_from(stream_id, after).await
+    }
+
+    async fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<EventStream<E>, Error> {
+        self.store.read_stream_with(stream_id, options).await
+    }
+
+    async fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<Option<EventStreamVersion>, Error> {
+        self.store.head_version::<E>(stream_id, options).await
+    }
+}
+
+/// Prefixes whatever name the store's own strategy gives a stream with the tenant id.
+#[derive(Debug)]
+struct TenantStreamNaming {
+    tenant_id: String,
+    inner: Arc<dyn StreamNaming>,
+}
+
+impl StreamNaming for TenantStreamNaming {
+    fn stream_name(&self, stream_id: &EventStreamId) -> String {
+        format!("{}-{}", self.tenant_id, self.inner.stream_name(stream_id))
+    }
+
+    fn stream_id(&self, stream_name: &str) -> Option<EventStreamId> {
+        let inner = stream_name
+            .strip_prefix(self.tenant_id.as_str())?
+            .strip_prefix('-')?;
+        self.inner.stream_id(inner)
+    }
+}
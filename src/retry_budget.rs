@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// A pool of attempts shared by every `execute_with_budget` call it is passed to.
+/// Each attempt, first tries and retries alike, takes one; once it is empty the
+/// remaining commands fail with `Error::MaxRetriesExceeded` without running.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    remaining: Arc<AtomicU32>,
+}
+
+impl RetryBudget {
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            remaining: Arc::new(AtomicU32::new(attempts)),
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn try_consume(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |remaining| {
+                remaining.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_same_attempts() {
+        let budget = RetryBudget::new(2);
+        let shared = budget.clone();
+
+        assert!(budget.try_consume());
+        assert!(shared.try_consume());
+        assert!(!budget.try_consume());
+        assert_eq!(shared.remaining(), 0);
+    }
+}
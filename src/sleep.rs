@@ -0,0 +1,87 @@
+use futures::future::BoxFuture;
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// Waits out the retry delays inside `execute`. Supply your own through
+/// `ExecuteConfig::with_sleeper` to observe or skip the backoff in tests, or to
+/// use your async runtime's timer.
+pub trait Sleeper: Debug + Send + Sync {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Sleeps on the Tokio timer. This is the default when the `tokio` feature is
+/// enabled, and must be polled from within a Tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSleeper;
+
+#[cfg(feature = "tokio")]
+impl Sleeper for TokioSleeper {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Sleeps on a short-lived OS thread, so it works under any async runtime. This
+/// is the default without the `tokio` feature; retries are rare enough that a
+/// thread per delay is cheap.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadSleeper;
+
+impl Sleeper for ThreadSleeper {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = sender.send(());
+        });
+        Box::pin(async move {
+            let _ = receiver.await;
+        })
+    }
+}
+
+/// Sleeps like `ThreadSleeper`, but the thread exits as soon as the returned
+/// future is dropped instead of sleeping out the full `duration`, for timeouts
+/// that usually don't expire.
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn cancellable_sleep(duration: Duration) -> BoxFuture<'static, ()> {
+    let (cancel, cancelled) = std::sync::mpsc::channel::<()>();
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = cancelled.recv_timeout(duration) {
+            let _ = sender.send(());
+        }
+    });
+    Box::pin(async move {
+        let _cancel = cancel;
+        let _ = receiver.await;
+    })
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) type DefaultSleeper = TokioSleeper;
+
+#[cfg(not(feature = "tokio"))]
+pub(crate) type DefaultSleeper = ThreadSleeper;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn thread_sleeper_needs_no_runtime() {
+        let start = Instant::now();
+        futures::executor::block_on(ThreadSleeper.sleep(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn cancellable_sleeps_wait_out_their_duration() {
+        let start = Instant::now();
+        futures::executor::block_on(cancellable_sleep(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
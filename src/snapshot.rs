@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
+
+use crate::{Error, EventStreamId, EventStreamVersion};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    version: EventStreamVersion,
+    state: serde_json::Value,
+}
+
+impl Snapshot {
+    pub fn new(version: EventStreamVersion, state: serde_json::Value) -> Self {
+        Self { version, state }
+    }
+
+    pub fn version(&self) -> EventStreamVersion {
+        self.version
+    }
+
+    pub fn state(&self) -> &serde_json::Value {
+        &self.state
+    }
+}
+
+pub trait SnapshotStore: Debug + Send + Sync {
+    fn load(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<Option<Snapshot>, Error>>;
+
+    fn save(
+        &self,
+        stream_id: EventStreamId,
+        snapshot: Snapshot,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySnapshotStore {
+    snapshots: Arc<Mutex<HashMap<EventStreamId, Snapshot>>>,
+}
+
+impl InMemorySnapshotStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, stream_id: &EventStreamId) -> Option<Snapshot> {
+        self.snapshots.lock().unwrap().get(stream_id).cloned()
+    }
+}
+
+impl SnapshotStore for InMemorySnapshotStore {
+    fn load(&self, stream_id: EventStreamId) -> BoxFuture<'_, Result<Option<Snapshot>, Error>> {
+        let snapshot = self.get(&stream_id);
+        Box::pin(async move { Ok(snapshot) })
+    }
+
+    fn save(
+        &self,
+        stream_id: EventStreamId,
+        snapshot: Snapshot,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.snapshots.lock().unwrap().insert(stream_id, snapshot);
+        Box::pin(async { Ok(()) })
+    }
+}
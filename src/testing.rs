@@ -0,0 +1,364 @@
+//! Helpers for testing code built on `mneme` without a real server.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::error::version_mismatch;
+use crate::{
+    Error, Event, EventStore, EventStream, EventStreamId, EventStreamVersion, ExpectedVersion,
+    RawEvent, ReadOptions,
+};
+
+/// Wraps another store and injects faults into its operations, so the retry,
+/// timeout and error paths of `execute` can be exercised deterministically.
+/// Publishes are numbered from 1 across `publish`, `publish_with_ids` and
+/// `publish_serialized`; a faulted publish never reaches the inner store.
+#[derive(Debug)]
+pub struct FaultInjectingStore<S> {
+    inner: S,
+    failures: Mutex<HashMap<usize, Error>>,
+    conflicts: Mutex<HashSet<usize>>,
+    read_delay: Option<Duration>,
+    publishes: AtomicUsize,
+    reads: AtomicUsize,
+}
+
+impl<S: EventStore> FaultInjectingStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            failures: Mutex::new(HashMap::new()),
+            conflicts: Mutex::new(HashSet::new()),
+            read_delay: None,
+            publishes: AtomicUsize::new(0),
+            reads: AtomicUsize::new(0),
+        }
+    }
+
+    /// Fails the `n`th publish with `error`.
+    pub fn fail_publish(mut self, n: usize, error: Error) -> Self {
+        self.failures.get_mut().unwrap().insert(n, error);
+        self
+    }
+
+    /// Fails the `n`th publish with `Error::EventStoreVersionMismatch`, as if
+    /// another writer had appended one event first.
+    pub fn conflict_on_publish(mut self, n: usize) -> Self {
+        self.conflicts.get_mut().unwrap().insert(n);
+        self
+    }
+
+    /// Waits `delay` before every read.
+    pub fn with_read_delay(mut self, delay: Duration) -> Self {
+        self.read_delay = Some(delay);
+        self
+    }
+
+    /// How many publishes were attempted, faulted ones included.
+    pub fn publish_count(&self) -> usize {
+        self.publishes.load(Ordering::Relaxed)
+    }
+
+    /// How many reads were started.
+    pub fn read_count(&self) -> usize {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn before_publish(
+        &self,
+        stream_id: &EventStreamId,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        let publish = self.publishes.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(error) = self.failures.lock().unwrap().remove(&publish) {
+            return Err(error);
+        }
+        if self.conflicts.lock().unwrap().remove(&publish) {
+            let actual = expected_version.map_or(EventStreamVersion::ZERO, |v| v + 1);
+            // `execute` publishes without an expected version only to a stream it
+            // found empty, so the rival write is the one that created it.
+            let expected =
+                expected_version.map_or(ExpectedVersion::NoStream, ExpectedVersion::Exact);
+            return Err(version_mismatch(stream_id.clone(), expected, Some(actual)));
+        }
+        Ok(())
+    }
+
+    async fn before_read(&self) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        if let Some(delay) = self.read_delay {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+impl<S: EventStore + Send + Sync> EventStore for FaultInjectingStore<S> {
+    async fn publish<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.before_publish(&stream_id, expected_version)?;
+        self.inner
+            .publish(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn publish_with_ids<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<(Uuid, E)>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.before_publish(&stream_id, expected_version)?;
+        self.inner
+            .publish_with_ids(stream_id, events, expected_version)
+            .await
+    }
+
+    async fn publish_serialized<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        events: Vec<E>,
+        serialized: Vec<RawEvent>,
+        expected_version: Option<EventStreamVersion>,
+    ) -> Result<(), Error> {
+        self.before_publish(&stream_id, expected_version)?;
+        self.inner
+            .publish_serialized(stream_id, events, serialized, expected_version)
+            .await
+    }
+
+    async fn read_stream<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+    ) -> Result<EventStream<E>, Error> {
+        self.before_read().await;
+        self.inner.read_stream(stream_id).await
+    }
+
+    async fn read_stream_from<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        after: EventStreamVersion,
+    ) -> Result<EventStream<E>, Error> {
+        self.before_read().await;
+        self.inner.read_stream_from(stream_id, after).await
+    }
+
+    async fn read_stream_with<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<EventStream<E>, Error> {
+        self.before_read().await;
+        self.inner.read_stream_with(stream_id, options).await
+    }
+
+    async fn head_version<E: Event>(
+        &self,
+        stream_id: EventStreamId,
+        options: ReadOptions,
+    ) -> Result<Option<EventStreamVersion>, Error> {
+        self.before_read().await;
+        self.inner.head_version::<E>(stream_id, options).await
+    }
+}
+
+/// Panics unless `E::event_type_table` matches `expected` exactly, naming every
+/// variant whose event type changed, that disappeared, or that is missing from
+/// `expected`. Renaming a stored event type leaves the events already written
+/// under it unreadable, so pin the table in a test:
+///
+/// ```ignore
+/// assert_event_type_stability::<AccountEvent>(&[
+///     ("Opened", "AccountEvent.Opened"),
+///     ("Closed", "account.closed"),
+/// ]);
+/// ```
+pub fn assert_event_type_stability<E: Event>(expected: &[(&str, &str)]) {
+    let actual = E::event_type_table();
+    let mut problems = vec![];
+    for &(variant, event_type) in expected {
+        match actual.iter().find(|(name, _)| *name == variant) {
+            Some((_, actual_type)) if *actual_type == event_type => {}
+            Some((_, actual_type)) => problems.push(format!(
+                "{variant}: expected \"{event_type}\", found \"{actual_type}\""
+            )),
+            None => problems.push(format!(
+                "{variant}: expected \"{event_type}\", but there is no such variant"
+            )),
+        }
+    }
+    for (variant, event_type) in &actual {
+        if !expected.iter().any(|(name, _)| name == variant) {
+            problems.push(format!("{variant}: \"{event_type}\" is not pinned"));
+        }
+    }
+    assert!(
+        problems.is_empty(),
+        "event types of {} changed:\n{}",
+        std::any::type_name::<E>(),
+        problems.join("\n")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{Command, CommandOutcome, ExecuteConfig, InMemoryEventStore, Sleeper, execute};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Pinged {
+        id: Uuid,
+    }
+
+    impl Event for Pinged {
+        fn event_type(&self) -> String {
+            "Pinged".to_string()
+        }
+
+        fn event_type_table() -> Vec<(&'static str, &'static str)> {
+            vec![("Pinged", "Pinged")]
+        }
+    }
+
+    #[derive(Clone)]
+    struct Ping {
+        id: Uuid,
+    }
+
+    impl Command for Ping {
+        type Event = Pinged;
+        type State = ();
+        type Error = Infallible;
+
+        fn handle(&self) -> Result<Vec<Pinged>, Self::Error> {
+            Ok(vec![Pinged { id: self.id }])
+        }
+        fn event_stream_id(&self) -> EventStreamId {
+            EventStreamId(self.id)
+        }
+        fn get_state(&self) -> Self::State {}
+        fn set_state(&mut self, _: &Self::State) {}
+    }
+
+    #[derive(Debug)]
+    struct NoSleep;
+
+    impl Sleeper for NoSleep {
+        fn sleep(&self, _: Duration) -> futures::future::BoxFuture<'static, ()> {
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn injected_conflicts_are_retried() {
+        let store = FaultInjectingStore::new(InMemoryEventStore::new())
+            .conflict_on_publish(1)
+            .conflict_on_publish(2);
+        let id = Uuid::new_v4();
+        let config = ExecuteConfig::default()
+            .with_jitter(false)
+            .with_sleeper(NoSleep);
+
+        let outcome = execute(Ping { id }, &store, config).await.unwrap();
+
+        assert_eq!(
+            outcome,
+            CommandOutcome::Committed {
+                events: vec![Pinged { id }],
+                version: EventStreamVersion::ZERO,
+                replayed_events: 0,
+                positions: vec![],
+                per_attempt_delays: vec![Duration::from_millis(100), Duration::from_millis(200)],
+            }
+        );
+        assert_eq!(outcome.total_backoff(), Duration::from_millis(300));
+        assert_eq!(store.publish_count(), 3);
+        assert!(store.read_count() >= 3);
+    }
+
+    #[tokio::test]
+    async fn injected_conflicts_on_new_streams_expect_no_stream() {
+        let store = FaultInjectingStore::new(InMemoryEventStore::new()).conflict_on_publish(1);
+        let id = Uuid::new_v4();
+
+        let result = store
+            .publish(EventStreamId(id), vec![Pinged { id }], None)
+            .await;
+
+        let Err(Error::EventStoreVersionMismatch {
+            expected, actual, ..
+        }) = result
+        else {
+            panic!("Expected a version mismatch, got: {:?}", result);
+        };
+        assert_eq!(expected, ExpectedVersion::NoStream);
+        assert_eq!(actual, Some(EventStreamVersion::ZERO));
+    }
+
+    #[tokio::test]
+    async fn injected_failures_are_returned() {
+        let store = FaultInjectingStore::new(InMemoryEventStore::new()).fail_publish(
+            1,
+            Error::DeadlineExceeded {
+                stream: "injected".to_string(),
+            },
+        );
+
+        let result = execute(Ping { id: Uuid::new_v4() }, &store, Default::default()).await;
+
+        assert!(matches!(result, Err(Error::DeadlineExceeded { .. })));
+        assert_eq!(store.publish_count(), 1);
+    }
+
+    #[test]
+    fn pinned_event_types_pass() {
+        assert_event_type_stability::<Pinged>(&[("Pinged", "Pinged")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Pinged: expected \"ping\", found \"Pinged\"")]
+    fn changed_event_types_fail() {
+        assert_event_type_stability::<Pinged>(&[("Pinged", "ping")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "\"Pinged\" is not pinned")]
+    fn unpinned_event_types_fail() {
+        assert_event_type_stability::<Pinged>(&[]);
+    }
+
+    #[tokio::test]
+    async fn reads_can_be_delayed() {
+        let store = FaultInjectingStore::new(InMemoryEventStore::new())
+            .with_read_delay(Duration::from_millis(50));
+        let started = std::time::Instant::now();
+
+        store
+            .read_stream::<Pinged>(EventStreamId::new())
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(store.read_count(), 1);
+    }
+}
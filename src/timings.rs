@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+/// Where `execute_timed` spent its time. `read` covers replays and the reads made
+/// to rebase onto concurrent writes, `publish` the appends (including any
+/// read-back of event positions), and `backoff` the sleeps between attempts.
+/// `attempts` counts the first attempt and every retry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecuteTimings {
+    pub total: Duration,
+    pub read: Duration,
+    pub handle: Duration,
+    pub publish: Duration,
+    pub backoff: Duration,
+    pub attempts: u32,
+}
@@ -0,0 +1,153 @@
+#![cfg(feature = "derive")]
+
+use mneme::{
+    AggregateState, Command, CommandOutcome, Event, EventStore, EventStreamId, EventStreamVersion,
+    InMemoryEventStore, command, execute,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize, Event)]
+enum CounterEvent {
+    Added {
+        amount: u32,
+    },
+    #[event(type = "counter.total")]
+    Total {
+        value: u32,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Event)]
+struct Reset;
+
+#[derive(Debug, Deserialize, Serialize, Event)]
+#[event(type = "counter.renamed")]
+struct Renamed(String);
+
+#[derive(Clone, Debug, Default)]
+struct CounterState {
+    sum: u32,
+}
+
+impl AggregateState<CounterEvent> for CounterState {
+    fn apply(&self, event: &CounterEvent) -> Self {
+        match event {
+            CounterEvent::Added { amount } => Self {
+                sum: self.sum + amount,
+            },
+            CounterEvent::Total { .. } => self.clone(),
+        }
+    }
+}
+
+#[command(event = CounterEvent)]
+#[derive(Clone)]
+struct RecordTotal {
+    #[stream_id]
+    id: Uuid,
+    #[state]
+    state: CounterState,
+}
+
+impl RecordTotal {
+    fn handle(&self) -> Result<Vec<CounterEvent>, std::convert::Infallible> {
+        Ok(vec![CounterEvent::Total {
+            value: self.state.sum,
+        }])
+    }
+}
+
+#[command(event = CounterEvent)]
+#[derive(Clone)]
+struct Touch {
+    #[stream_id]
+    id: EventStreamId,
+}
+
+impl Touch {
+    fn handle(&self) -> Result<Vec<CounterEvent>, std::convert::Infallible> {
+        Ok(vec![])
+    }
+}
+
+#[tokio::test]
+async fn command_macro_generates_the_scaffolding() {
+    let event_store = InMemoryEventStore::new();
+    let id = Uuid::new_v4();
+
+    event_store
+        .publish(
+            EventStreamId(id),
+            vec![
+                CounterEvent::Added { amount: 2 },
+                CounterEvent::Added { amount: 3 },
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let command = RecordTotal {
+        id,
+        state: CounterState::default(),
+    };
+    assert_eq!(command.event_stream_id(), EventStreamId(id));
+
+    let outcome = execute(command, &event_store, Default::default())
+        .await
+        .expect("failed to execute command");
+    assert_eq!(
+        outcome,
+        CommandOutcome::Committed {
+            events: vec![CounterEvent::Total { value: 5 }],
+            version: EventStreamVersion::new(2),
+            replayed_events: 2,
+            positions: vec![],
+            per_attempt_delays: vec![],
+        }
+    );
+
+    let touch = Touch {
+        id: EventStreamId(id),
+    };
+    let outcome = execute(touch, &event_store, Default::default())
+        .await
+        .expect("failed to execute command");
+    assert!(outcome.is_noop());
+}
+
+#[test]
+fn event_derive_names_variants_and_structs() {
+    assert_eq!(
+        CounterEvent::Added { amount: 1 }.event_type(),
+        "CounterEvent.Added"
+    );
+    assert_eq!(
+        CounterEvent::Total { value: 1 }.event_type(),
+        "counter.total"
+    );
+    assert_eq!(
+        CounterEvent::all_event_types(),
+        vec!["CounterEvent.Added", "counter.total"]
+    );
+
+    assert_eq!(
+        CounterEvent::event_type_table(),
+        vec![("Added", "CounterEvent.Added"), ("Total", "counter.total")]
+    );
+
+    assert_eq!(Reset.event_type(), "Reset");
+    assert_eq!(Reset::event_type_table(), vec![("Reset", "Reset")]);
+    assert_eq!(Reset::all_event_types(), vec!["Reset"]);
+    assert_eq!(Renamed("x".to_string()).event_type(), "counter.renamed");
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn derived_event_types_can_be_pinned() {
+    mneme::testing::assert_event_type_stability::<CounterEvent>(&[
+        ("Added", "CounterEvent.Added"),
+        ("Total", "counter.total"),
+    ]);
+}
@@ -0,0 +1,72 @@
+mod test_cases;
+
+use mneme::{EventStore, EventStreamId, InMemoryEventStore};
+use test_cases::*;
+
+impl TestStore for InMemoryEventStore {
+    fn create_test_store() -> Self {
+        InMemoryEventStore::new()
+    }
+
+    async fn read_client_events(event_store: &Self, stream_id: EventStreamId) -> Vec<TestEvent> {
+        let mut stream = event_store
+            .read_stream(stream_id)
+            .await
+            .expect("failed to read stream");
+        let mut events = vec![];
+        while let Some((event, _)) = stream.next().await.expect("failed to get next event") {
+            events.push(event);
+        }
+        events
+    }
+}
+
+#[tokio::test]
+async fn successful_command_execution_with_no_events_produced() {
+    test_successful_command_execution_with_no_events_produced::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn command_rejection_error() {
+    test_command_rejection_error::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn successful_execution_with_events_will_record_events() {
+    test_successful_execution_with_events_will_record_events::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn stateless_commands_append_at_the_head() {
+    test_stateless_commands_append_at_the_head::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn a_panicking_handler_fails_the_command() {
+    test_a_panicking_handler_fails_the_command::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn typed_streams_publish_and_read_their_event_type() {
+    test_typed_streams_publish_and_read_their_event_type::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn nil_stream_ids_are_rejected() {
+    test_nil_stream_ids_are_rejected::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn existing_events_are_available_to_handler() {
+    test_existing_events_are_available_to_handler::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn reading_a_missing_stream_yields_no_events() {
+    test_reading_a_missing_stream_yields_no_events::<InMemoryEventStore>().await
+}
+
+#[tokio::test]
+async fn creating_an_existing_stream_fails() {
+    test_creating_an_existing_stream_fails::<InMemoryEventStore>().await
+}
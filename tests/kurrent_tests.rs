@@ -1,3 +1,5 @@
+#![cfg(feature = "kurrent")]
+
 mod test_cases;
 
 use test_cases::*;
@@ -51,7 +53,37 @@ async fn successful_execution_with_events_will_record_events() {
     test_successful_execution_with_events_will_record_events::<Kurrent>().await
 }
 
+#[tokio::test]
+async fn stateless_commands_append_at_the_head() {
+    test_stateless_commands_append_at_the_head::<Kurrent>().await
+}
+
+#[tokio::test]
+async fn a_panicking_handler_fails_the_command() {
+    test_a_panicking_handler_fails_the_command::<Kurrent>().await
+}
+
+#[tokio::test]
+async fn typed_streams_publish_and_read_their_event_type() {
+    test_typed_streams_publish_and_read_their_event_type::<Kurrent>().await
+}
+
+#[tokio::test]
+async fn nil_stream_ids_are_rejected() {
+    test_nil_stream_ids_are_rejected::<Kurrent>().await
+}
+
 #[tokio::test]
 async fn existing_events_are_available_to_handler() {
     test_existing_events_are_available_to_handler::<Kurrent>().await
 }
+
+#[tokio::test]
+async fn reading_a_missing_stream_yields_no_events() {
+    test_reading_a_missing_stream_yields_no_events::<Kurrent>().await
+}
+
+#[tokio::test]
+async fn creating_an_existing_stream_fails() {
+    test_creating_an_existing_stream_fails::<Kurrent>().await
+}
@@ -1,9 +1,12 @@
-use mneme::{AggregateState, Command, Error, Event, EventStore, EventStreamId, execute};
+use mneme::{
+    AggregateState, Command, CommandOutcome, Error, Event, EventStore, EventStreamId,
+    EventStreamVersion, ExpectedVersion, TypedStream, create, execute,
+};
 use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
 use uuid::Uuid;
 
-pub trait TestStore: EventStore + Send {
+pub trait TestStore: EventStore + Send + Sync {
     fn create_test_store() -> Self;
 
     #[allow(async_fn_in_trait)]
@@ -93,6 +96,9 @@ impl Command for EventProducingCommand {
     }
     fn get_state(&self) -> Self::State {}
     fn set_state(&mut self, _: &Self::State) {}
+    fn needs_replay(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -102,17 +108,18 @@ pub struct StatefulCommandState {
 }
 
 impl AggregateState<TestEvent> for StatefulCommandState {
-    fn apply(&mut self, event: &TestEvent) -> &Self {
+    fn apply(&self, event: &TestEvent) -> Self {
+        let mut state = self.clone();
         match event {
             TestEvent::FooHappened { value, .. } => {
-                self.foo = Some(*value);
+                state.foo = Some(*value);
             }
             TestEvent::BarHappened { value, .. } => {
-                self.bar = Some(*value);
+                state.bar = Some(*value);
             }
             _ => (),
         }
-        self
+        state
     }
 }
 
@@ -181,7 +188,7 @@ impl Event for TestEvent {
 }
 
 pub async fn test_successful_command_execution_with_no_events_produced<Adapter: TestStore>() {
-    let mut event_store: Adapter = TestStore::create_test_store();
+    let event_store: Adapter = TestStore::create_test_store();
     let command = NoopCommand::new();
     let stream_id = command.event_stream_id();
 
@@ -190,12 +197,19 @@ pub async fn test_successful_command_execution_with_no_events_produced<Adapter:
         .await
         .expect("Failed to publish");
 
-    let result = execute(command, &mut event_store, Default::default()).await;
-    assert!(result.is_ok());
+    let result = execute(command, &event_store, Default::default()).await;
+    assert_eq!(
+        result.expect("failed to execute command"),
+        CommandOutcome::NoOp {
+            version: Some(EventStreamVersion::new(0)),
+            replayed_events: 1,
+            per_attempt_delays: vec![],
+        }
+    );
 }
 
 pub async fn test_command_rejection_error<Adapter: TestStore>() {
-    let mut event_store: Adapter = TestStore::create_test_store();
+    let event_store: Adapter = TestStore::create_test_store();
     let command = RejectCommand::new();
     let stream_id = command.event_stream_id();
 
@@ -204,31 +218,26 @@ pub async fn test_command_rejection_error<Adapter: TestStore>() {
         .await
         .expect("Failed to publish");
 
-    match execute(command, &mut event_store, Default::default()).await {
-        Err(Error::CommandFailed {
-            source,
-            message,
-            attempt: _,
-            max_attempts: _,
-        }) => {
-            if let Some(reject_error) = source.downcast_ref::<RejectCommandError>() {
-                assert_eq!(reject_error.to_string(), "Command failed: no");
-                assert_eq!(message, "Command failed: no");
-            } else {
-                panic!("Unexpected error type: {:?}", source);
-            }
+    match execute(command, &event_store, Default::default()).await {
+        Err(error @ Error::CommandFailed { .. }) => {
+            let Some(reject_error) = error.command_error::<RejectCommandError>() else {
+                panic!("Unexpected error type: {:?}", error);
+            };
+            assert_eq!(reject_error.to_string(), "Command failed: no");
+            assert!(error.command_error::<std::fmt::Error>().is_none());
+            assert!(error.to_string().ends_with("Command failed: no"));
         }
-        Ok(()) => panic!("Expected command to be rejected."),
+        Ok(_) => panic!("Expected command to be rejected."),
         Err(other) => panic!("Unexpected error: {:?}", other),
     }
 }
 
 pub async fn test_successful_execution_with_events_will_record_events<Adapter: TestStore>() {
-    let mut event_store: Adapter = TestStore::create_test_store();
+    let event_store: Adapter = TestStore::create_test_store();
     let id = Uuid::new_v4();
     let command = EventProducingCommand::new(id);
 
-    let result = execute(command, &mut event_store, Default::default()).await;
+    let result = execute(command, &event_store, Default::default()).await;
     result.expect("failed to execute command");
 
     let events = TestStore::read_client_events(&event_store, EventStreamId(id)).await;
@@ -236,8 +245,35 @@ pub async fn test_successful_execution_with_events_will_record_events<Adapter: T
     assert_eq!(events, vec![TestEvent::One { id }, TestEvent::Two { id }])
 }
 
+pub async fn test_stateless_commands_append_at_the_head<Adapter: TestStore>() {
+    let event_store: Adapter = TestStore::create_test_store();
+    let id = Uuid::new_v4();
+
+    event_store
+        .publish(EventStreamId(id), vec![TestEvent::One { id }], None)
+        .await
+        .unwrap();
+
+    let result = execute(
+        EventProducingCommand::new(id),
+        &event_store,
+        Default::default(),
+    )
+    .await;
+    assert_eq!(
+        result.expect("failed to execute command"),
+        CommandOutcome::Committed {
+            events: vec![TestEvent::One { id }, TestEvent::Two { id }],
+            version: EventStreamVersion::new(2),
+            replayed_events: 0,
+            positions: vec![],
+            per_attempt_delays: vec![],
+        }
+    );
+}
+
 pub async fn test_existing_events_are_available_to_handler<Adapter: TestStore>() {
-    let mut event_store: Adapter = TestStore::create_test_store();
+    let event_store: Adapter = TestStore::create_test_store();
     let id = Uuid::new_v4();
     let rand_1: u16 = rand::random();
     let rand_2: u16 = rand::random();
@@ -254,8 +290,8 @@ pub async fn test_existing_events_are_available_to_handler<Adapter: TestStore>()
         .unwrap();
 
     let command = StatefulCommand::new(id);
-    match execute(command, &mut event_store, Default::default()).await {
-        Ok(()) => {
+    match execute(command, &event_store, Default::default()).await {
+        Ok(CommandOutcome::Committed { .. }) => {
             assert_eq!(
                 TestStore::read_client_events(&event_store, EventStreamId(id)).await,
                 vec![
@@ -268,3 +304,136 @@ pub async fn test_existing_events_are_available_to_handler<Adapter: TestStore>()
         other => panic!("Unexpected result: {:?}", other),
     };
 }
+
+pub async fn test_a_panicking_handler_fails_the_command<Adapter: TestStore>() {
+    let event_store: Adapter = TestStore::create_test_store();
+    let id = Uuid::new_v4();
+
+    // With no history, `StatefulCommand::handle` unwraps state that was never set.
+    match execute(StatefulCommand::new(id), &event_store, Default::default()).await {
+        Err(Error::CommandFailed { message, .. }) => {
+            assert!(message.contains("handle panicked"), "{message}");
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert!(
+        TestStore::read_client_events(&event_store, EventStreamId(id))
+            .await
+            .is_empty()
+    );
+}
+
+pub async fn test_typed_streams_publish_and_read_their_event_type<Adapter: TestStore>() {
+    let event_store: Adapter = TestStore::create_test_store();
+    let id = Uuid::new_v4();
+    let stream = TypedStream::<TestEvent>::new(EventStreamId(id));
+
+    event_store
+        .publish_typed(&stream, vec![TestEvent::One { id }], None)
+        .await
+        .unwrap();
+
+    let mut events = event_store.read_typed(&stream).await.unwrap();
+    assert_eq!(
+        events.next().await.unwrap(),
+        Some((TestEvent::One { id }, EventStreamVersion::new(0)))
+    );
+    assert_eq!(events.next().await.unwrap(), None);
+    assert_eq!(
+        TestStore::read_client_events(&event_store, stream.into_id()).await,
+        vec![TestEvent::One { id }]
+    );
+}
+
+pub async fn test_nil_stream_ids_are_rejected<Adapter: TestStore>() {
+    let event_store: Adapter = TestStore::create_test_store();
+
+    let published = event_store
+        .publish(
+            EventStreamId::nil(),
+            vec![TestEvent::One { id: Uuid::nil() }],
+            None,
+        )
+        .await;
+    let read = event_store
+        .read_stream::<TestEvent>(EventStreamId::nil())
+        .await;
+    let executed = execute(
+        EventProducingCommand::new(Uuid::nil()),
+        &event_store,
+        Default::default(),
+    )
+    .await;
+
+    for result in [
+        published.map(|_| ()),
+        read.map(|_| ()),
+        executed.map(|_| ()),
+    ] {
+        match result {
+            Err(Error::InvalidConfig { parameter, .. }) => {
+                assert_eq!(parameter, Some("stream_id".to_string()));
+            }
+            other => panic!("Expected the nil stream id to be rejected, got {:?}", other),
+        }
+    }
+}
+
+pub async fn test_reading_a_missing_stream_yields_no_events<Adapter: TestStore>() {
+    let event_store: Adapter = TestStore::create_test_store();
+
+    let mut stream = event_store
+        .read_stream::<TestEvent>(EventStreamId::new())
+        .await
+        .expect("reading a missing stream should not fail");
+    assert!(
+        stream
+            .next()
+            .await
+            .expect("failed to read missing stream")
+            .is_none()
+    );
+}
+
+pub async fn test_creating_an_existing_stream_fails<Adapter: TestStore>() {
+    let event_store: Adapter = TestStore::create_test_store();
+    let id = Uuid::new_v4();
+
+    let created = create(
+        EventProducingCommand::new(id),
+        &event_store,
+        Default::default(),
+    )
+    .await
+    .expect("failed to create stream");
+    assert_eq!(
+        created,
+        CommandOutcome::Committed {
+            events: vec![TestEvent::One { id }, TestEvent::Two { id }],
+            version: EventStreamVersion::new(1),
+            replayed_events: 0,
+            positions: vec![],
+            per_attempt_delays: vec![],
+        }
+    );
+
+    match create(
+        EventProducingCommand::new(id),
+        &event_store,
+        Default::default(),
+    )
+    .await
+    {
+        Err(Error::EventStoreVersionMismatch {
+            expected, actual, ..
+        }) => {
+            assert_eq!(expected, ExpectedVersion::NoStream);
+            assert_eq!(actual, Some(EventStreamVersion::new(1)));
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert_eq!(
+        TestStore::read_client_events(&event_store, EventStreamId(id)).await,
+        vec![TestEvent::One { id }, TestEvent::Two { id }]
+    );
+}